@@ -0,0 +1,313 @@
+// use dependencies
+use rodio::{OutputStream, Source};
+use crate::synth::{Envelope, Instrument, Oscillator};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::{thread, time::Duration};
+
+pub const SAMPLE_RATE: u32 = 44_100;
+
+// VoiceId struct, which voice a NoteOff releases, so notes on the same
+// key started by different keys or by playback are released separately
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct VoiceId(pub u64);
+
+// SoundRequest enum, the commands which are sent to the audio engine
+// variants:
+// 1. NoteOn        -> Start a voice with the given id at the given frequency and
+//                     volume (0-100), released after the duration in seconds or,
+//                     if there is no duration, when a NoteOff for its id arrives
+// 2. NoteOff       -> Release the voice with the given id
+// 3. SetInstrument -> Change the instrument used by voices started from now on
+#[derive(Debug, Clone)]
+pub enum SoundRequest {
+    NoteOn { id: VoiceId, frequency: f32, volume: f32, duration: Option<f32> },
+    NoteOff { id: VoiceId },
+    SetInstrument(Instrument),
+}
+
+// Voice struct, a single sounding note inside the Mixer
+// fields:
+// 1. id         -> The id the voice was started with
+// 2. frequency  -> The frequency of the voice in hertz
+// 3. amplitude  -> The peak amplitude of the voice
+// 4. envelope   -> The envelope shaping the amplitude
+// 5. oscillator -> The oscillator producing the wave
+// 6. elapsed   -> The amount of samples played so far
+// 7. length     -> The amount of samples before the voice is released,
+//                  or None if it is held until a NoteOff
+// 8. level      -> The envelope level of the last sample
+// 9. released   -> The sample the voice was released at and its level then
+#[derive(Debug, Clone)]
+struct Voice {
+    id: VoiceId,
+    frequency: f32,
+    amplitude: f32,
+    envelope: Envelope,
+    oscillator: Oscillator,
+    elapsed: u64,
+    length: Option<u64>,
+    level: f32,
+    released: Option<(u64, f32)>,
+}
+
+impl Voice {
+    fn next_sample(&mut self, sample_rate: u32) -> f32 {
+        if self.length.is_some_and(|length| self.elapsed >= length) {
+            self.release();
+        }
+
+        let time = self.elapsed as f32 / sample_rate as f32;
+        self.level = match self.released {
+            None => self.envelope.held_level(time),
+            Some((released_at, from)) => {
+                let since_release = (self.elapsed - released_at) as f32 / sample_rate as f32;
+                self.envelope.release_level(from, since_release)
+            }
+        };
+
+        let sample = self.oscillator.next_sample(self.frequency, sample_rate) * self.amplitude * self.level;
+        self.elapsed += 1;
+        sample
+    }
+
+    fn release(&mut self) {
+        if self.released.is_none() {
+            self.released = Some((self.elapsed, self.level));
+        }
+    }
+
+    fn is_finished(&self, sample_rate: u32) -> bool {
+        match self.released {
+            None => false,
+            Some((released_at, _)) => {
+                (self.elapsed - released_at) as f32 / sample_rate as f32 >= self.envelope.release
+            }
+        }
+    }
+}
+
+// Mixer struct, which owns every sounding voice and sums them
+// into a single stream of samples
+// fields:
+// 1. voices      -> The voices which are currently sounding
+// 2. instrument  -> The instrument used for new voices
+// 3. sample_rate -> The amount of samples per second
+//
+// functions:
+// 1. handle      -> Applies a SoundRequest to the voices
+// 2. next_sample -> Mixes the next sample of every voice, removing finished voices
+// 3. is_silent   -> Whether or not there are no voices left sounding
+#[derive(Debug, Clone)]
+pub struct Mixer {
+    voices: Vec<Voice>,
+    instrument: Instrument,
+    sample_rate: u32,
+}
+
+impl Mixer {
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            voices: Vec::new(),
+            instrument: Instrument::default(),
+            sample_rate,
+        }
+    }
+
+    pub fn handle(&mut self, request: SoundRequest) {
+        match request {
+            SoundRequest::NoteOn { id, frequency, volume, duration } => {
+                self.voices.push(Voice {
+                    id,
+                    frequency,
+                    amplitude: volume / 100.0,
+                    envelope: self.instrument.envelope,
+                    oscillator: Oscillator::new(self.instrument.waveform, self.instrument.pulse_width),
+                    elapsed: 0,
+                    length: duration.map(|duration| (duration * self.sample_rate as f32) as u64),
+                    level: 0.0,
+                    released: None,
+                });
+            }
+            SoundRequest::NoteOff { id } => {
+                for voice in self.voices.iter_mut().filter(|voice| voice.id == id) {
+                    voice.release();
+                }
+            }
+            SoundRequest::SetInstrument(instrument) => {
+                self.instrument = instrument;
+            }
+        }
+    }
+
+    pub fn next_sample(&mut self) -> f32 {
+        let sample_rate = self.sample_rate;
+        let sample: f32 = self.voices.iter_mut()
+            .map(|voice| voice.next_sample(sample_rate))
+            .sum();
+        self.voices.retain(|voice| !voice.is_finished(sample_rate));
+
+        sample.clamp(-1.0, 1.0)
+    }
+
+    pub fn is_silent(&self) -> bool {
+        self.voices.is_empty()
+    }
+}
+
+// EngineSource struct, the rodio Source played by the audio engine,
+// which takes requests from the channel before producing each sample
+struct EngineSource {
+    mixer: Mixer,
+    receiver: Receiver<SoundRequest>,
+}
+
+impl Iterator for EngineSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        while let Ok(request) = self.receiver.try_recv() {
+            self.mixer.handle(request);
+        }
+        Some(self.mixer.next_sample())
+    }
+}
+
+impl Source for EngineSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.mixer.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+// AudioEngine struct, a handle to the single long-lived audio thread
+// which owns the output stream and mixes every voice
+// fields:
+// 1. sender     -> The channel requests are sent to the audio thread through
+// 2. next_voice -> The id given to the next voice started, shared between clones
+//
+// functions:
+// 1. start   -> Spawns the audio thread and returns a handle to it
+//    channel -> A handle together with the receiving end of its requests,
+//               for whatever mixes them
+// 2. send    -> Sends a SoundRequest to the audio thread
+// 3. note_on -> Starts a voice and returns the id to release it with
+#[derive(Debug, Clone)]
+pub struct AudioEngine {
+    sender: Sender<SoundRequest>,
+    next_voice: Arc<AtomicU64>,
+}
+
+impl AudioEngine {
+    pub fn start() -> Self {
+        let (engine, receiver) = Self::channel();
+
+        thread::spawn(move || {
+            let (_stream, handle) = match OutputStream::try_default() {
+                Ok(output) => output,
+                Err(e) => {
+                    eprintln!("Failed to create output stream: {}", e);
+                    return;
+                }
+            };
+
+            let source = EngineSource {
+                mixer: Mixer::new(SAMPLE_RATE),
+                receiver,
+            };
+            if let Err(e) = handle.play_raw(source) {
+                eprintln!("Failed to start audio engine: {}", e);
+                return;
+            }
+
+            // the stream stops when it is dropped, so keep this thread alive
+            loop {
+                thread::park();
+            }
+        });
+
+        engine
+    }
+
+    pub fn channel() -> (Self, Receiver<SoundRequest>) {
+        let (sender, receiver) = mpsc::channel();
+        (Self { sender, next_voice: Arc::new(AtomicU64::new(0)) }, receiver)
+    }
+
+    pub fn send(&self, request: SoundRequest) {
+        // the audio thread only goes away if no output device was found,
+        // in which case there is nothing to play the request on
+        let _ = self.sender.send(request);
+    }
+
+    pub fn note_on(&self, frequency: f32, volume: f32, duration: Option<f32>) -> VoiceId {
+        let id = VoiceId(self.next_voice.fetch_add(1, Ordering::Relaxed));
+        self.send(SoundRequest::NoteOn { id, frequency, volume, duration });
+        id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::synth::Waveform;
+
+    fn test_mixer() -> Mixer {
+        let mut mixer = Mixer::new(8_000);
+        mixer.handle(SoundRequest::SetInstrument(Instrument {
+            waveform: Waveform::Square,
+            pulse_width: 0.5,
+            envelope: Envelope { attack: 0.0, decay: 0.0, sustain: 1.0, release: 0.0 },
+        }));
+        mixer
+    }
+
+    #[test]
+    fn voices_end_after_their_duration_or_note_off() {
+        let mut mixer = test_mixer();
+        assert!(mixer.is_silent());
+
+        // a tenth of a second at 8000 samples per second
+        mixer.handle(SoundRequest::NoteOn { id: VoiceId(0), frequency: 440.0, volume: 50.0, duration: Some(0.1) });
+        mixer.handle(SoundRequest::NoteOn { id: VoiceId(1), frequency: 220.0, volume: 50.0, duration: None });
+        for _ in 0..=800 {
+            mixer.next_sample();
+        }
+        assert_eq!(mixer.voices.len(), 1);
+
+        // a held voice only ends when it is let go of
+        for _ in 0..8_000 {
+            mixer.next_sample();
+        }
+        assert!(!mixer.is_silent());
+        mixer.handle(SoundRequest::NoteOff { id: VoiceId(1) });
+        mixer.next_sample();
+        assert!(mixer.is_silent());
+        assert_eq!(mixer.next_sample(), 0.0);
+    }
+
+    #[test]
+    fn note_off_releases_only_its_own_voice() {
+        let mut mixer = test_mixer();
+        // two held chords sharing the same G
+        mixer.handle(SoundRequest::NoteOn { id: VoiceId(0), frequency: 392.0, volume: 100.0, duration: None });
+        mixer.handle(SoundRequest::NoteOn { id: VoiceId(1), frequency: 392.0, volume: 100.0, duration: None });
+
+        mixer.handle(SoundRequest::NoteOff { id: VoiceId(0) });
+        mixer.next_sample();
+        assert_eq!(mixer.voices.iter().map(|voice| voice.id).collect::<Vec<VoiceId>>(), [VoiceId(1)]);
+        assert_eq!(mixer.next_sample().abs(), 1.0);
+    }
+}
@@ -1,8 +1,201 @@
 // use other files inside this project
-use crate::{Program, Note, RealNote, Playable, async_play_note};
+use crate::{Program, Note, RealNote, Playable};
+use crate::audio::{AudioEngine, VoiceId};
+use crate::scale::ScaleType;
+use crate::spelling::Spelling;
 
 // use dependencies
-use std::collections::HashMap;
+use strum_macros::Display;
+
+// ChordMode enum defines what is played when a single key is pressed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum ChordMode {
+    #[strum(to_string = "Single notes")]
+    Single,
+    #[strum(to_string = "Major triads")]
+    MajorTriads,
+    #[strum(to_string = "Diatonic triads")]
+    DiatonicTriads,
+    #[strum(to_string = "Diatonic sevenths")]
+    DiatonicSevenths,
+    #[strum(to_string = "Chord type")]
+    Quality,
+}
+
+impl ChordMode {
+    pub const ALL: [ChordMode; 5] = [
+        ChordMode::Single, ChordMode::MajorTriads, ChordMode::DiatonicTriads,
+        ChordMode::DiatonicSevenths, ChordMode::Quality
+    ];
+}
+
+// ChordQuality enum defines the kinds of chord which can be built on a root note
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Display)]
+pub enum ChordQuality {
+    Major,
+    #[strum(to_string = "Minor (m)")]
+    Minor,
+    #[strum(to_string = "Diminished (dim)")]
+    Diminished,
+    #[strum(to_string = "Augmented (aug)")]
+    Augmented,
+    #[strum(to_string = "Suspended 2nd (sus2)")]
+    Sus2,
+    #[strum(to_string = "Suspended 4th (sus4)")]
+    Sus4,
+    #[strum(to_string = "Sixth (6)")]
+    Sixth,
+    #[strum(to_string = "Minor sixth (m6)")]
+    MinorSixth,
+    #[strum(to_string = "Added 9th (add9)")]
+    Add9,
+    #[strum(to_string = "Major seventh (maj7)")]
+    MajorSeventh,
+    #[strum(to_string = "Minor seventh (m7)")]
+    MinorSeventh,
+    #[strum(to_string = "Dominant seventh (7)")]
+    DominantSeventh,
+    #[strum(to_string = "Half-diminished (m7b5)")]
+    HalfDiminished,
+    #[strum(to_string = "Diminished seventh (dim7)")]
+    DiminishedSeventh,
+    #[strum(to_string = "Ninth (9)")]
+    Ninth,
+    #[strum(to_string = "Eleventh (11)")]
+    Eleventh,
+    #[strum(to_string = "Thirteenth (13)")]
+    Thirteenth,
+    #[strum(to_string = "Flat nine (7b9)")]
+    SevenFlatNine,
+    #[strum(to_string = "Sharp nine (7#9)")]
+    SevenSharpNine,
+    #[strum(to_string = "Flat five (7b5)")]
+    SevenFlatFive,
+    #[strum(to_string = "Sharp five (7#5)")]
+    SevenSharpFive,
+    #[strum(to_string = "Altered (7alt)")]
+    Altered,
+}
+
+// implement the ChordQuality enum
+// functions:
+// 1. intervals -> The semitones from the root to each note of the chord
+// 2. symbol    -> The symbol written after the root in a chord name, such as "m7"
+impl ChordQuality {
+    pub const ALL: [ChordQuality; 22] = [
+        ChordQuality::Major, ChordQuality::Minor, ChordQuality::Diminished, ChordQuality::Augmented,
+        ChordQuality::Sus2, ChordQuality::Sus4, ChordQuality::Sixth, ChordQuality::MinorSixth,
+        ChordQuality::Add9, ChordQuality::MajorSeventh, ChordQuality::MinorSeventh,
+        ChordQuality::DominantSeventh, ChordQuality::HalfDiminished, ChordQuality::DiminishedSeventh,
+        ChordQuality::Ninth, ChordQuality::Eleventh, ChordQuality::Thirteenth,
+        ChordQuality::SevenFlatNine, ChordQuality::SevenSharpNine, ChordQuality::SevenFlatFive,
+        ChordQuality::SevenSharpFive, ChordQuality::Altered
+    ];
+
+    pub fn intervals(&self) -> &'static [i32] {
+        match self {
+            ChordQuality::Major => &[0, 4, 7],
+            ChordQuality::Minor => &[0, 3, 7],
+            ChordQuality::Diminished => &[0, 3, 6],
+            ChordQuality::Augmented => &[0, 4, 8],
+            ChordQuality::Sus2 => &[0, 2, 7],
+            ChordQuality::Sus4 => &[0, 5, 7],
+            ChordQuality::Sixth => &[0, 4, 7, 9],
+            ChordQuality::MinorSixth => &[0, 3, 7, 9],
+            ChordQuality::Add9 => &[0, 4, 7, 14],
+            ChordQuality::MajorSeventh => &[0, 4, 7, 11],
+            ChordQuality::MinorSeventh => &[0, 3, 7, 10],
+            ChordQuality::DominantSeventh => &[0, 4, 7, 10],
+            ChordQuality::HalfDiminished => &[0, 3, 6, 10],
+            ChordQuality::DiminishedSeventh => &[0, 3, 6, 9],
+            ChordQuality::Ninth => &[0, 4, 7, 10, 14],
+            // the 3rd is left out as it clashes with the 11th
+            ChordQuality::Eleventh => &[0, 7, 10, 14, 17],
+            // the 11th is left out as it clashes with the 3rd
+            ChordQuality::Thirteenth => &[0, 4, 7, 10, 14, 21],
+            ChordQuality::SevenFlatNine => &[0, 4, 7, 10, 13],
+            ChordQuality::SevenSharpNine => &[0, 4, 7, 10, 15],
+            ChordQuality::SevenFlatFive => &[0, 4, 6, 10],
+            ChordQuality::SevenSharpFive => &[0, 4, 8, 10],
+            // the 3rd and 7th with every alteration of the 9th and 5th/13th
+            ChordQuality::Altered => &[0, 4, 10, 13, 15, 20],
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            ChordQuality::Major => "",
+            ChordQuality::Minor => "m",
+            ChordQuality::Diminished => "dim",
+            ChordQuality::Augmented => "aug",
+            ChordQuality::Sus2 => "sus2",
+            ChordQuality::Sus4 => "sus4",
+            ChordQuality::Sixth => "6",
+            ChordQuality::MinorSixth => "m6",
+            ChordQuality::Add9 => "add9",
+            ChordQuality::MajorSeventh => "maj7",
+            ChordQuality::MinorSeventh => "m7",
+            ChordQuality::DominantSeventh => "7",
+            ChordQuality::HalfDiminished => "m7b5",
+            ChordQuality::DiminishedSeventh => "dim7",
+            ChordQuality::Ninth => "9",
+            ChordQuality::Eleventh => "11",
+            ChordQuality::Thirteenth => "13",
+            ChordQuality::SevenFlatNine => "7b9",
+            ChordQuality::SevenSharpNine => "7#9",
+            ChordQuality::SevenFlatFive => "7b5",
+            ChordQuality::SevenSharpFive => "7#5",
+            ChordQuality::Altered => "7alt",
+        }
+    }
+}
+
+// Inversion enum defines which note of a chord is played lowest
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum Inversion {
+    #[strum(to_string = "Root position")]
+    Root,
+    #[strum(to_string = "1st inversion")]
+    First,
+    #[strum(to_string = "2nd inversion")]
+    Second,
+    #[strum(to_string = "3rd inversion")]
+    Third,
+}
+
+impl Inversion {
+    pub const ALL: [Inversion; 4] = [Inversion::Root, Inversion::First, Inversion::Second, Inversion::Third];
+
+    // the number of notes moved from the bottom of the chord to the top
+    pub fn notes_moved(&self) -> usize {
+        match self {
+            Inversion::Root => 0,
+            Inversion::First => 1,
+            Inversion::Second => 2,
+            Inversion::Third => 3,
+        }
+    }
+}
+
+// Voicing enum defines how the notes of a chord are spread across octaves
+// variants:
+// 1. Closed -> Every note as close to the one below it as possible
+// 2. Open   -> Every other note raised an octave, spreading the chord out
+// 3. Drop2  -> The second highest note dropped an octave
+// 4. Drop3  -> The third highest note dropped an octave
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum Voicing {
+    Closed,
+    Open,
+    #[strum(to_string = "Drop 2")]
+    Drop2,
+    #[strum(to_string = "Drop 3")]
+    Drop3,
+}
+
+impl Voicing {
+    pub const ALL: [Voicing; 4] = [Voicing::Closed, Voicing::Open, Voicing::Drop2, Voicing::Drop3];
+}
 
 // Chord struct, which is used to play multiple notes at once
 // and calculations musically relevant to this concept
@@ -15,77 +208,266 @@ pub struct Chord {
 // 1. triad_from_note   -> Calculates the major triad 
 //                         (the 1st, 3rd and 5th notes of the major scale)
 //                         and returns it as a function
-// 2. get_major_scale  -> returns the major scale of the relevant Note
+// 2. diatonic_from_note -> The chord of the given size built by stacking thirds
+//                         of the scale on the note, or None if the note is not in it
+// 3. from_quality      -> The chord of the given ChordQuality rooted on the note
+// 4. voiced            -> The chord with the given inversion and voicing,
+//                         with its notes in order of pitch
+// 5. sort_by_pitch     -> Puts the notes of the chord in order from lowest to highest
+// 6. from_program      -> The chord played for the note in the program's ChordMode
+//                         and voicing
+// 7. name              -> The name of the chord the notes make, such as "Cmaj7/E",
+//                         spelt for the key, or None if they do not make a known chord
+// 8. is_note_in_scale -> Whether the note is in the scale selected in the program
+// 9. get_major_scale  -> returns the major scale of the relevant Note
 //
 // also implements:
-// 1. play (from Playable) -> The same as plays the chord asynchronously using
-//                            async_play_note
+// 1. play (from Playable)  -> Plays every note of the chord at once
+// 2. start (from Playable) -> Starts every note of the chord until their voices are released
 
 impl Chord {
     pub fn is_note_in_scale(program: &Program, note: Note) -> bool {
         match &program.selected_scale {
             None => true,
-            Some(scale_root) => Chord::get_major_scale(*scale_root).contains(&note)
+            Some(scale_root) => program.scale_type.notes(*scale_root).contains(&note)
         }
     }
 
     pub fn triad_from_note(note: &RealNote) -> Chord {
-        let scale = Self::get_major_scale(note.note.clone());
-        return Chord{
+        let scale = Self::get_major_scale(note.pitch.note());
+        // the 3rd and 5th sit above the root, even when they wrap past B
+        let above_root = |degree: usize| (scale[degree].index() as i32 - scale[0].index() as i32).rem_euclid(12);
+        Chord{
             notes: vec![
-                RealNote { note: scale[0].clone(), length: note.length.clone(), octave: note.octave },
-                RealNote { note: scale[2].clone(), length: note.length.clone(), octave: note.octave },
-                RealNote { note: scale[4].clone(), length: note.length.clone(), octave: note.octave }
+                note.clone(),
+                note.transposed(above_root(2)),
+                note.transposed(above_root(4))
             ]
         }
     }
-     pub fn get_major_scale(note: Note) -> Vec<Note> { 
-        let mut major_scales: HashMap<Note, Vec<Note>> = HashMap::new();
-        major_scales.insert(Note::A, vec![
-            Note::A, Note::B, Note::Csharp, Note::D, Note::E, Note::Fsharp, Note::Gsharp
-        ]);
-        major_scales.insert(Note::Asharp, vec![
-            Note::Asharp, Note::C, Note::D, Note::Dsharp, Note::F, Note::G, Note::A
-        ]);
-        major_scales.insert(Note::B, vec![
-            Note::B, Note::Csharp, Note::Dsharp, Note::E, Note::Fsharp, Note::Gsharp, Note::Asharp
-        ]);
-        major_scales.insert(Note::C, vec![
-            Note::C, Note::D, Note::E, Note::F, Note::G, Note::A, Note::B
-        ]);
-        major_scales.insert(Note::Csharp, vec![
-            Note::Csharp, Note::Dsharp, Note::F, Note::Fsharp, Note::Gsharp, Note::Asharp, Note::C
-        ]);
-        major_scales.insert(Note::D, vec![
-            Note::D, Note::E, Note::Fsharp, Note::G, Note::A, Note::B, Note::Csharp
-        ]);
-        major_scales.insert(Note::Dsharp, vec![
-            Note::Dsharp, Note::F, Note::G, Note::Gsharp, Note::Asharp, Note::C, Note::D
-        ]);
-        major_scales.insert(Note::E, vec![
-            Note::E, Note::Fsharp, Note::Gsharp, Note::A, Note::B, Note::Csharp, Note::Dsharp
-        ]);
-        major_scales.insert(Note::F, vec![
-            Note::F, Note::G, Note::A, Note::Asharp, Note::C, Note::D, Note::E
-        ]);
-        major_scales.insert(Note::Fsharp, vec![
-            Note::Fsharp, Note::Gsharp, Note::Asharp, Note::B, Note::Csharp, Note::Dsharp, Note::F
-        ]);
-        major_scales.insert(Note::G, vec![
-            Note::G, Note::A, Note::B, Note::C, Note::D, Note::E, Note::Fsharp
-        ]);
-        major_scales.insert(Note::Gsharp, vec![
-            Note::Gsharp, Note::Asharp, Note::C, Note::Csharp, Note::Dsharp, Note::F, Note::G
-        ]);
-        major_scales.insert(Note::None, Note::ALL.to_vec());
 
-        major_scales.get(&note).expect("Not a valid scale").clone()
+    pub fn diatonic_from_note(note: &RealNote, scale_root: Note, scale_type: ScaleType, size: usize) -> Option<Chord> {
+        let degree = scale_type.notes(scale_root).iter().position(|scale_note| *scale_note == note.pitch.note())?;
+        let intervals = scale_type.intervals();
+
+        // every other note of the scale, counting up from the pressed note
+        let mut semitones = 0;
+        let mut notes = vec![note.clone()];
+        for step in (degree..).step_by(2).take(size - 1) {
+            semitones += intervals[step % intervals.len()] + intervals[(step + 1) % intervals.len()];
+            notes.push(note.transposed(semitones as i32));
+        }
+        Some(Chord { notes })
+    }
+
+    pub fn from_quality(note: &RealNote, quality: ChordQuality) -> Chord {
+        Chord {
+            notes: quality.intervals().iter().map(|semitones| note.transposed(*semitones)).collect()
+        }
+    }
+
+    pub fn voiced(mut self, inversion: Inversion, voicing: Voicing) -> Chord {
+        self.sort_by_pitch();
+        let note_count = self.notes.len();
+        if note_count < 2 {
+            return self;
+        }
+
+        // a triad has no 3rd inversion, so it stays in its 2nd
+        for note in self.notes.iter_mut().take(inversion.notes_moved().min(note_count - 1)) {
+            *note = note.transposed(12);
+        }
+        self.sort_by_pitch();
+
+        let dropped = match voicing {
+            Voicing::Closed => None,
+            Voicing::Open => {
+                for note in self.notes.iter_mut().skip(1).step_by(2) {
+                    *note = note.transposed(12);
+                }
+                None
+            }
+            Voicing::Drop2 => note_count.checked_sub(2).filter(|_| note_count >= 3),
+            Voicing::Drop3 => note_count.checked_sub(3).filter(|_| note_count >= 4),
+        };
+        if let Some(index) = dropped {
+            self.notes[index] = self.notes[index].transposed(-12);
+        }
+        self.sort_by_pitch();
+        self
+    }
+
+    pub fn sort_by_pitch(&mut self) {
+        self.notes.sort_by_key(|note| note.pitch);
+    }
+
+    pub fn from_program(program: &Program, note: RealNote) -> Chord {
+        // without a scale selected, the chords of the note's own major scale are used
+        let (scale_root, scale_type) = match program.selected_scale {
+            Some(scale_root) => (scale_root, program.scale_type),
+            None => (note.pitch.note(), ScaleType::Major),
+        };
+        let diatonic = |size| Chord::diatonic_from_note(&note, scale_root, scale_type, size)
+            .unwrap_or_else(|| Chord { notes: vec![note.clone()] });
+
+        let chord = match program.chord_mode {
+            ChordMode::Single => return Chord { notes: vec![note] },
+            ChordMode::MajorTriads => Chord::triad_from_note(&note),
+            ChordMode::DiatonicTriads => diatonic(3),
+            ChordMode::DiatonicSevenths => diatonic(4),
+            ChordMode::Quality => Chord::from_quality(&note, program.chord_quality),
+        };
+        chord.voiced(program.inversion, program.voicing)
+    }
+
+    pub fn name(&self, spelling: &Spelling) -> Option<String> {
+        // the notes played, ignoring which octave they are in
+        let mut pitch_classes: Vec<i32> = self.notes.iter()
+            .map(|note| note.pitch.note().index() as i32)
+            .collect();
+        pitch_classes.sort();
+        pitch_classes.dedup();
+        if pitch_classes.len() < 3 {
+            return None;
+        }
+        let bass = self.notes.iter().map(|note| note.pitch).min()?.note();
+
+        let mut matches = Vec::new();
+        for root in &pitch_classes {
+            for quality in ChordQuality::ALL {
+                let mut chord_classes: Vec<i32> = quality.intervals().iter()
+                    .map(|interval| (root + interval) % 12)
+                    .collect();
+                chord_classes.sort();
+                chord_classes.dedup();
+                if chord_classes == pitch_classes {
+                    matches.push((Note::from_index(*root as usize), quality));
+                }
+            }
+        }
+
+        // a chord rooted on the bass note is named without a slash,
+        // so C E G A is C6 rather than Am7/C
+        let (root, quality) = matches.iter()
+            .find(|(root, _)| *root == bass)
+            .or(matches.first())?;
+        let root_name = spelling.name(*root);
+        if *root == bass {
+            Some(format!("{}{}", root_name, quality.symbol()))
+        } else {
+            // the bass is spelt as a note of the chord, such as the E# of C#/E#
+            let bass_name = Spelling::chord_note(root_name, (bass.index() + 12 - root.index()) % 12, bass);
+            Some(format!("{}{}/{}", root_name, quality.symbol(), bass_name))
+        }
+    }
+
+    pub fn get_major_scale(note: Note) -> Vec<Note> {
+        ScaleType::Major.notes(note)
     }
 }
 
 // implement Playable trait for Chord
 impl Playable for Chord { 
-    fn play(&self, bpm: f32, is_recording: bool, volume: f32) {
-        async_play_note(&self.notes, bpm, is_recording, volume);
+    fn play(&self, engine: &AudioEngine, bpm: f32, is_recording: bool, volume: f32) {
+        for note in &self.notes {
+            note.play(engine, bpm, is_recording, volume);
+        }
+    }
+
+    fn start(&self, engine: &AudioEngine, volume: f32) -> Vec<VoiceId> {
+        self.notes.iter()
+            .flat_map(|note| note.start(engine, volume))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{NoteLength, Pitch};
+    use crate::test_helpers::{chord_pitches, real_note};
+
+    #[test]
+    fn diatonic_triads_follow_the_scale_degree() {
+        let chord = Chord::diatonic_from_note(&real_note(Note::D), Note::C, ScaleType::Major, 3).unwrap();
+        assert_eq!(chord_pitches(&chord), vec![(Note::D, 4), (Note::F, 4), (Note::A, 4)]);
+
+        let chord = Chord::diatonic_from_note(&real_note(Note::B), Note::C, ScaleType::Major, 3).unwrap();
+        assert_eq!(chord_pitches(&chord), vec![(Note::B, 4), (Note::D, 5), (Note::F, 5)]);
+
+        let chord = Chord::diatonic_from_note(&real_note(Note::E), Note::A, ScaleType::HarmonicMinor, 3).unwrap();
+        assert_eq!(chord_pitches(&chord), vec![(Note::E, 4), (Note::Gsharp, 4), (Note::B, 4)]);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn diatonic_sevenths_add_the_next_third() {
+        let chord = Chord::diatonic_from_note(&real_note(Note::G), Note::C, ScaleType::Major, 4).unwrap();
+        assert_eq!(chord_pitches(&chord), vec![(Note::G, 4), (Note::B, 4), (Note::D, 5), (Note::F, 5)]);
+    }
+
+    #[test]
+    fn chord_qualities_stack_their_intervals_on_the_root() {
+        let chord = Chord::from_quality(&real_note(Note::A), ChordQuality::HalfDiminished);
+        assert_eq!(chord_pitches(&chord), vec![(Note::A, 4), (Note::C, 5), (Note::Dsharp, 5), (Note::G, 5)]);
+
+        let chord = Chord::from_quality(&real_note(Note::C), ChordQuality::Thirteenth);
+        assert_eq!(chord_pitches(&chord), vec![
+            (Note::C, 4), (Note::E, 4), (Note::G, 4), (Note::Asharp, 4), (Note::D, 5), (Note::A, 5)
+        ]);
+    }
+
+    #[test]
+    fn triads_are_built_upwards_from_the_root() {
+        let chord = Chord::triad_from_note(&real_note(Note::B));
+        assert_eq!(chord_pitches(&chord), vec![(Note::B, 4), (Note::Dsharp, 5), (Note::Fsharp, 5)]);
+    }
+
+    #[test]
+    fn inversions_move_the_lowest_notes_up_an_octave() {
+        let triad = || Chord::from_quality(&real_note(Note::C), ChordQuality::Major);
+
+        let chord = triad().voiced(Inversion::First, Voicing::Closed);
+        assert_eq!(chord_pitches(&chord), vec![(Note::E, 4), (Note::G, 4), (Note::C, 5)]);
+
+        let chord = triad().voiced(Inversion::Third, Voicing::Closed);
+        assert_eq!(chord_pitches(&chord), vec![(Note::G, 4), (Note::C, 5), (Note::E, 5)]);
+    }
+
+    #[test]
+    fn voicings_spread_the_chord() {
+        let seventh = || Chord::from_quality(&real_note(Note::C), ChordQuality::MajorSeventh);
+
+        let chord = seventh().voiced(Inversion::Root, Voicing::Open);
+        assert_eq!(chord_pitches(&chord), vec![(Note::C, 4), (Note::G, 4), (Note::E, 5), (Note::B, 5)]);
+
+        let chord = seventh().voiced(Inversion::Root, Voicing::Drop2);
+        assert_eq!(chord_pitches(&chord), vec![(Note::G, 3), (Note::C, 4), (Note::E, 4), (Note::B, 4)]);
+
+        let chord = seventh().voiced(Inversion::Root, Voicing::Drop3);
+        assert_eq!(chord_pitches(&chord), vec![(Note::E, 3), (Note::C, 4), (Note::G, 4), (Note::B, 4)]);
+    }
+
+    #[test]
+    fn sounding_notes_are_named_as_chords() {
+        let chord = |notes: &[(Note, i32)]| Chord {
+            notes: notes.iter().map(|(note, octave)| RealNote { pitch: Pitch::new(*note, *octave), length: NoteLength::Quarter }).collect()
+        };
+
+        assert_eq!(chord(&[(Note::C, 4), (Note::E, 4), (Note::G, 4)]).name(&Spelling::default()), Some("C".to_string()));
+        assert_eq!(chord(&[(Note::E, 4), (Note::G, 4), (Note::B, 4), (Note::C, 5)]).name(&Spelling::default()), Some("Cmaj7/E".to_string()));
+        assert_eq!(chord(&[(Note::Fsharp, 4), (Note::A, 4), (Note::C, 5), (Note::E, 5)]).name(&Spelling::default()), Some("F#m7b5".to_string()));
+        assert_eq!(chord(&[(Note::C, 4), (Note::E, 4), (Note::G, 4), (Note::A, 4)]).name(&Spelling::default()), Some("C6".to_string()));
+        assert_eq!(chord(&[(Note::C, 4), (Note::E, 4)]).name(&Spelling::default()), None);
+
+        // chords are spelt for the key they are played in
+        let a_flat_major = Spelling::for_key(Note::Gsharp, ScaleType::Major);
+        assert_eq!(chord(&[(Note::Csharp, 4), (Note::F, 4), (Note::Gsharp, 4)]).name(&a_flat_major), Some("Db".to_string()));
+        assert_eq!(chord(&[(Note::F, 4), (Note::Gsharp, 4), (Note::Csharp, 5)]).name(&Spelling::default()), Some("C#/E#".to_string()));
+        assert_eq!(chord(&[(Note::C, 4), (Note::Csharp, 4), (Note::D, 4)]).name(&Spelling::default()), None);
+    }
+
+    #[test]
+    fn notes_outside_the_scale_have_no_diatonic_chord() {
+        assert!(Chord::diatonic_from_note(&real_note(Note::Csharp), Note::C, ScaleType::Major, 3).is_none());
+    }
+}
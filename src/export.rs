@@ -0,0 +1,69 @@
+// use dependencies
+use chrono::{DateTime, Local};
+use std::path::{Path, PathBuf};
+use strum_macros::Display;
+
+// ExportKind enum defines the kinds of file a Song can be exported as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum ExportKind {
+    #[strum(to_string = "MIDI")]
+    Midi,
+    #[strum(to_string = "WAV")]
+    Wav,
+}
+
+// implement the ExportKind enum
+// functions:
+// 1. extension      -> The file extension used for the kind of file
+// 2. with_extension -> The path with its extension replaced by this kind's
+// 3. take_path      -> A path in the directory named after the time of the take,
+//                      such as take-2026-10-17-1432.mid, which does not exist yet
+impl ExportKind {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportKind::Midi => "mid",
+            ExportKind::Wav => "wav",
+        }
+    }
+
+    pub fn with_extension(&self, path: &Path) -> PathBuf {
+        path.with_extension(self.extension())
+    }
+
+    pub fn take_path(&self, directory: &Path, time: DateTime<Local>) -> PathBuf {
+        let name = time.format("take-%Y-%m-%d-%H%M").to_string();
+        let mut path = directory.join(format!("{}.{}", name, self.extension()));
+
+        // more than one take in the same minute gets a number on the end
+        let mut number = 2;
+        while path.exists() {
+            path = directory.join(format!("{}-{}.{}", name, number, self.extension()));
+            number += 1;
+        }
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+    use std::fs;
+
+    #[test]
+    fn takes_are_named_after_their_time_without_overwriting() {
+        let directory = std::env::temp_dir().join(format!("rmk-export-{}", std::process::id()));
+        fs::create_dir_all(&directory).unwrap();
+        let time = Local.with_ymd_and_hms(2026, 10, 17, 14, 32, 5).unwrap();
+
+        let first = ExportKind::Midi.take_path(&directory, time);
+        assert_eq!(first, directory.join("take-2026-10-17-1432.mid"));
+
+        fs::write(&first, []).unwrap();
+        let second = ExportKind::Midi.take_path(&directory, time);
+        assert_eq!(second, directory.join("take-2026-10-17-1432-2.mid"));
+        assert_eq!(ExportKind::Wav.with_extension(&second), directory.join("take-2026-10-17-1432-2.wav"));
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
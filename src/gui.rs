@@ -1,71 +1,65 @@
-use iced::{alignment, border::Radius, font::Weight, widget::{self, MouseArea, button, checkbox, container, pick_list, slider, text, text_input, Space}, Border, Color, Font, Length, Theme};
-use crate::{Message, Note, Program, Chord};
-use std::{collections::HashMap, sync::{Arc, Mutex}};
-use std::string::ToString;
+use iced::{alignment, widget::{self, button, canvas, checkbox, container, pick_list, scrollable, slider, text, text_input, Canvas, Space}, Length};
+use crate::{Message, Note, Pitch, Program, Spelling, Chord, ChordMode, ChordQuality, Inversion, Key, KeyboardRange, KeymapControl, KeymapNote, KeymapPreset, Piano, PlaybackState, ScaleType, Voicing, Waveform, WavFormat};
+use std::collections::HashMap;
+use std::fmt;
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum CurrentMenu {
-    Standard, Help,
+    Standard, Help, Keymap,
 }
 
 // allows Note to be converted to String
-impl ToString for Note {
-    fn to_string(&self) -> String {
-        match self {
-            Note::C => "C".to_string(),
-            Note::Csharp => "C#".to_string(),
-            Note::D => "D".to_string(),
-            Note::Dsharp => "D#".to_string(),
-            Note::E => "E".to_string(),
-            Note::F => "F".to_string(),
-            Note::Fsharp => "F#".to_string(),
-            Note::G => "G".to_string(),
-            Note::Gsharp => "G#".to_string(),
-            Note::A => "A".to_string(),
-            Note::Asharp => "A#".to_string(),
-            Note::B => "B".to_string(),
-            Note::None => "None".to_string(),
-        }
+impl fmt::Display for Note {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Note::C => "C",
+            Note::Csharp => "C#",
+            Note::D => "D",
+            Note::Dsharp => "D#",
+            Note::E => "E",
+            Note::F => "F",
+            Note::Fsharp => "F#",
+            Note::G => "G",
+            Note::Gsharp => "G#",
+            Note::A => "A",
+            Note::Asharp => "A#",
+            Note::B => "B",
+        };
+        write!(f, "{}", name)
     }
 }
 
 impl Program {
-    fn button_style(_theme: &Theme, _status: button::Status, note_color: Color, in_scale: bool, pressed: bool) -> button::Style {
-        let color = if pressed { 
-            Color {
-                a: 0.2,
-                ..note_color
-            }
-        } else if in_scale {
-            note_color 
-        } else {
-            Color {
-                a: 0.5,
-                ..note_color
-            }
-        };
+    // the name shown on a key, which is the spelling of the selected key
+    // or both names of a black key when no key is selected
+    fn key_label(&self, pitch: Pitch) -> String {
+        let sharp = Spelling::default().pitch_name(pitch);
+        let flat = Spelling::with_accidentals(-1).pitch_name(pitch);
 
-        button::Style {
-            background: Some(iced::Background::Color(color)),
-            text_color: if note_color == Color::BLACK { Color::WHITE } else { Color::BLACK },
-            border: Border { radius: Radius::from(2), ..Border::default() },
-            ..button::Style::default()
+        if self.selected_scale.is_some() {
+            self.spelling().pitch_name(pitch)
+        } else if sharp != flat {
+            format!("{}\n{}", flat, sharp)
+        } else {
+            sharp
         }
     }
-    
 
-    pub fn get_ui_information(&self, buttons_pressed: Arc<Mutex<HashMap<Note, bool>>>) -> iced::widget::Container<Message> {
+    pub fn get_ui_information(&self, buttons_pressed: HashMap<Pitch, bool>) -> iced::widget::Container<'_, Message> {
         match self.current_menu { 
             CurrentMenu::Standard => {
-                Self::standard_ui(&self, buttons_pressed)
+                Self::standard_ui(self, buttons_pressed)
             },
             CurrentMenu::Help => {
-                Self::help_ui(&self)
+                Self::help_ui(self)
+            }
+            CurrentMenu::Keymap => {
+                Self::keymap_ui(self)
             }
         }
     }
 
-    fn help_ui(&self) -> iced::widget::Container<Message> { 
+    fn help_ui(&self) -> iced::widget::Container<'_, Message> { 
         container(widget::column![
             text("How do I use this?")
                 .width(Length::Fill)
@@ -75,7 +69,7 @@ impl Program {
                 .width(Length::Fill)
                 .align_x(alignment::Horizontal::Center)
                 .size(30),
-            text("- Windows: C:\\Users\\USERNAME\\Documents\\RustMusicKeyboard\\output.mid\n- Linux: /tmp/output.mid\n- Other: ./output.mid")
+            text("Each take is saved as take-DATE-TIME.mid in Documents/RustMusicKeyboard,\nor in the folder chosen under \"Save recordings in\". Use \"Save as\" to save it elsewhere.")
                 .width(Length::Fill)
                 .align_x(alignment::Horizontal::Center)
                 .size(20),
@@ -87,13 +81,66 @@ impl Program {
         ].height(Length::Fill))
     }
 
-    fn standard_ui(&self, buttons_pressed: Arc<Mutex<HashMap<Note, bool>>>) -> iced::widget::Container<Message> {
-        let accidental_height = 132.6;
-        let accidental_width = 63.75;
-        let natural_height = 255.0;
-        let natural_width = 85.0;
+    // the editor for which computer keys play which notes
+    fn keymap_ui(&self) -> iced::widget::Container<'_, Message> {
+        let keymap = &self.settings.keymap;
 
-        container(widget::column![
+        container(scrollable(widget::column![
+            text("Keyboard mapping")
+                .width(Length::Fill)
+                .align_x(alignment::Horizontal::Center)
+                .size(40),
+            widget::row!(
+                text("Preset:"),
+                pick_list(
+                    KeymapPreset::ALL,
+                    KeymapPreset::ALL.into_iter().find(|preset| preset.to_string() == keymap.name),
+                    Message::KeymapPresetChange
+                ).placeholder("Custom").width(Length::Fixed(200.0)),
+            ).spacing(10),
+            text("Keys are written as the character they type, such as \"a\" or \";\",\nor by name, such as \"Space\" or \"ArrowUp\""),
+            widget::column(KeymapControl::ALL.map(|control| widget::row!(
+                text(control.to_string()).width(Length::Fixed(120.0)),
+                text_input("Key", keymap.control(control))
+                    .on_input(move |key| Message::KeymapControlChange(control, key))
+                    .padding(2)
+                    .width(Length::Fixed(120.0)),
+            ).spacing(10).into())).spacing(10),
+            widget::column(keymap.notes.iter().enumerate().map(|(index, (key, semitones))| widget::row!(
+                text_input("Key", key)
+                    .on_input(move |key| Message::KeymapKeyChange(index, key))
+                    .padding(2)
+                    .width(Length::Fixed(120.0)),
+                pick_list(
+                    KeymapNote::all(),
+                    Some(KeymapNote(*semitones)),
+                    move |note| Message::KeymapNoteChange(index, note)
+                ).width(Length::Fixed(200.0)),
+                button(text("Remove")).on_press(Message::RemoveKeymapKey(index)),
+            ).spacing(10).into())).spacing(10),
+            widget::row!(
+                button(text("Add key")).on_press(Message::AddKeymapKey),
+                button(text("Done")).on_press(Message::ToggleKeymapEditor),
+            ).spacing(10),
+        ].spacing(20).padding(10)))
+    }
+
+    // the on screen keyboard for the selected range, with each key's label,
+    // whether it is in the selected scale and whether it is pressed
+    fn keyboard(&self, buttons_pressed: &HashMap<Pitch, bool>) -> Canvas<Piano, Message> {
+        let mut piano = Piano::new(&self.settings.keyboard_range.pitches(self.octave), self.settings.key_zoom);
+        for key in &mut piano.keys {
+            key.label = self.key_label(key.pitch);
+            key.in_scale = Chord::is_note_in_scale(self, key.pitch.note());
+            key.pressed = buttons_pressed.get(&key.pitch) == Some(&true);
+        }
+
+        let size = piano.size();
+        canvas(piano).width(size.width).height(size.height)
+    }
+
+    fn standard_ui(&self, buttons_pressed: HashMap<Pitch, bool>) -> iced::widget::Container<'_, Message> {
+        container(scrollable(widget::column![
             widget::row!(
                 text("Note Length"),
                 slider(
@@ -119,304 +166,61 @@ impl Program {
             ).spacing(10).padding(5),
 
             widget::row!(
-                checkbox("Play major scale triads", self.play_chords)
-                    .on_toggle(|_| Message::PlayChords)
-                    .spacing(10),
-                
-                checkbox("Play asynchronously", self.play_async)
-                    .on_toggle(|_| Message::PlayAsync)
-                    .spacing(10),
-            ).spacing(20).padding(5),
+                text("Chords:"),
+                pick_list(
+                    ChordMode::ALL,
+                    Some(self.chord_mode),
+                    Message::ChordModeChange
+                ).width(Length::Fixed(180.0)),
+            ).spacing(20).padding(5).push_maybe(
+                (self.chord_mode == ChordMode::Quality).then(|| pick_list(
+                    ChordQuality::ALL,
+                    Some(self.chord_quality),
+                    Message::ChordQualityChange
+                ).width(Length::Fixed(250.0)))
+            ).push_maybe(
+                (self.chord_mode != ChordMode::Single).then(|| widget::row!(
+                    pick_list(Inversion::ALL, Some(self.inversion), Message::InversionChange)
+                        .width(Length::Fixed(150.0)),
+                    pick_list(Voicing::ALL, Some(self.voicing), Message::VoicingChange)
+                        .width(Length::Fixed(100.0)),
+                ).spacing(10))
+            ).push(
+                checkbox("Hold notes while pressed", self.hold_notes)
+                    .on_toggle(|_| Message::HoldNotes)
+                    .spacing(10)
+            ).push(
+                checkbox(format!("Sustain pedal ({})", self.settings.keymap.sustain), self.sustain_pedal)
+                    .on_toggle(Message::SustainPedal)
+                    .spacing(10)
+            ),
 
             Space::with_height(20), 
 
             widget::row!(
                 text("Octave:"),
-                button("+").on_press(Message::OctaveChange((self.octave + 1.0).min(6.0))),
-                button("-").on_press(Message::OctaveChange((self.octave - 1.0).max(0.0))),
-            ).spacing(10).padding(5),
-
-            Space::with_height(50), 
-
-            widget::row!(
-                widget::stack!(
-                    widget::row!(
-                        MouseArea::new(
-                            button(text(format!("C{}", self.octave))
-                                .size(24)
-                                .align_x(alignment::Horizontal::Center)
-                                .align_y(alignment::Vertical::Bottom)
-                                .font(Font { weight: Weight::Bold, ..Default::default() })
-                            )
-                            .style({
-                                let buttons = buttons_pressed.clone();
-                                move |theme, status| {
-                                    let is_pressed = *buttons.lock().unwrap().get(&Note::C).unwrap_or(&false);
-                                
-                                    Self::button_style(theme, status, Color::WHITE, Chord::is_note_in_scale(&self, Note::C), is_pressed)
-                                }
-                            })
-                            .width(Length::Fixed(natural_width))
-                            .height(Length::Fixed(natural_height))
-                            .padding(5)
-                        )
-                        .on_press(Message::Play(Note::C, true))
-                        .on_release(Message::EndPlaying(Note::C))
-                        .on_exit(Message::EndPlaying(Note::C)),
-
-                        MouseArea::new(
-                            button(text(format!("D{}", self.octave))
-                                .size(24)
-                                .align_x(alignment::Horizontal::Center)
-                                .align_y(alignment::Vertical::Bottom)
-                                .font(Font { weight: Weight::Bold, ..Default::default() })
-                            )
-                            .style({                            
-                                let buttons = buttons_pressed.clone();
-                                move |theme, status| {
-                                    let is_pressed = *buttons.lock().unwrap().get(&Note::D).unwrap_or(&false);
-                                    Self::button_style(theme, status, Color::WHITE, Chord::is_note_in_scale(&self, Note::D), is_pressed)
-                                }
-                            })
-                            .width(Length::Fixed(natural_width))
-                            .height(Length::Fixed(natural_height))
-                            .padding(5)
-                        )
-                        .on_press(Message::Play(Note::D, true))
-                        .on_release(Message::EndPlaying(Note::D))
-                        .on_exit(Message::EndPlaying(Note::D)),
-
-
-                        MouseArea::new(
-                            button(text(format!("E{}", self.octave))
-                                .size(24)
-                                .align_x(alignment::Horizontal::Center)
-                                .align_y(alignment::Vertical::Bottom)
-                                .font(Font { weight: Weight::Bold, ..Default::default() })
-                            )
-                            .style({
-                                let buttons = buttons_pressed.clone();
-                                move |theme, status| {
-                                    let is_pressed = *buttons.lock().unwrap().get(&Note::E).unwrap_or(&false);
-                                    Self::button_style(theme, status, Color::WHITE, Chord::is_note_in_scale(&self, Note::E), is_pressed)
-                                }
-                            })
-                            .width(Length::Fixed(natural_width))
-                            .height(Length::Fixed(natural_height))
-                            .padding(5)
-                        )
-                        .on_press(Message::Play(Note::E, true))
-                        .on_release(Message::EndPlaying(Note::E))
-                        .on_exit(Message::EndPlaying(Note::E)),
-
-                        MouseArea::new(
-                            button(text(format!("F{}", self.octave))
-                                .size(24)
-                                .align_x(alignment::Horizontal::Center)
-                                .align_y(alignment::Vertical::Bottom)
-                                .font(Font { weight: Weight::Bold, ..Default::default() })
-                            )
-                            .style({
-                                let buttons = buttons_pressed.clone();
-                                move |theme, status| {
-                                    let is_pressed = *buttons.lock().unwrap().get(&Note::F).unwrap_or(&false);
-                                    Self::button_style(theme, status, Color::WHITE, Chord::is_note_in_scale(&self, Note::F), is_pressed)
-                                }
-                            })
-                            .width(Length::Fixed(natural_width))
-                            .height(Length::Fixed(natural_height))
-                            .padding(5)
-                        )
-                        .on_press(Message::Play(Note::F, true))
-                        .on_release(Message::EndPlaying(Note::F))
-                        .on_exit(Message::EndPlaying(Note::F)),
-
-                        MouseArea::new(
-                            button(text(format!("G{}", self.octave))
-                                .size(24)
-                                .align_x(alignment::Horizontal::Center)
-                                .align_y(alignment::Vertical::Bottom)
-                                .font(Font { weight: Weight::Bold, ..Default::default() })
-                            )
-                            .style({
-                                let buttons = buttons_pressed.clone();
-                                move |theme, status| {
-                                    let is_pressed = *buttons.lock().unwrap().get(&Note::G).unwrap_or(&false);
-                                    Self::button_style(theme, status, Color::WHITE, Chord::is_note_in_scale(&self, Note::G), is_pressed)
-                                }
-                            })
-                            .width(Length::Fixed(natural_width))
-                            .height(Length::Fixed(natural_height))
-                            .padding(5)
-                        )
-                        .on_press(Message::Play(Note::G, true))
-                        .on_release(Message::EndPlaying(Note::G))
-                        .on_exit(Message::EndPlaying(Note::G)),
-
-                        MouseArea::new(
-                            button(text(format!("A{}", self.octave))
-                                .size(24)
-                                .align_x(alignment::Horizontal::Center)
-                                .align_y(alignment::Vertical::Bottom)
-                                .font(Font { weight: Weight::Bold, ..Default::default() })
-                            )
-                            .style({
-                                let buttons = buttons_pressed.clone();
-                                move |theme, status| {
-                                    let is_pressed = *buttons.lock().unwrap().get(&Note::A).unwrap_or(&false);
-                                    Self::button_style(theme, status, Color::WHITE, Chord::is_note_in_scale(&self, Note::A), is_pressed)
-                                }
-                            })
-                            .width(Length::Fixed(natural_width))
-                            .height(Length::Fixed(natural_height))
-                            .padding(5)
-                        )
-                        .on_press(Message::Play(Note::A, true))
-                        .on_release(Message::EndPlaying(Note::A))
-                        .on_exit(Message::EndPlaying(Note::A)),
-
-                        MouseArea::new(
-                            button(text(format!("B{}", self.octave))
-                                .size(24)
-                                .align_x(alignment::Horizontal::Center)
-                                .align_y(alignment::Vertical::Bottom)
-                                .font(Font { weight: Weight::Bold, ..Default::default() })
-                            )
-                            .style({
-                                let buttons = buttons_pressed.clone();
-                                move |theme, status| {
-                                    let is_pressed = *buttons.lock().unwrap().get(&Note::B).unwrap_or(&false);
-                                    Self::button_style(theme, status, Color::WHITE, Chord::is_note_in_scale(&self, Note::B), is_pressed)
-                                }
-                            })
-                            .width(Length::Fixed(natural_width))
-                            .height(Length::Fixed(natural_height))
-                            .padding(5)
-                        )
-                        .on_press(Message::Play(Note::B, true))
-                        .on_release(Message::EndPlaying(Note::B))
-                        .on_exit(Message::EndPlaying(Note::B)),
-                                    
-                                    ).spacing(2).padding(5),
-                
-                    widget::row!(
-                        Space::with_width(59.5),
-                        MouseArea::new(
-                            button(text(format!("Db{}\nC#{}", self.octave, self.octave))
-                                .size(24)
-                                .align_x(alignment::Horizontal::Center)
-                                .align_y(alignment::Vertical::Bottom)
-                                .font(Font { weight: Weight::Bold, ..Default::default() })
-                            )
-                            .style({
-                                let buttons = buttons_pressed.clone();
-                                move |theme, status| {
-                                    let is_pressed = *buttons.lock().unwrap().get(&Note::Csharp).unwrap_or(&false);
-                                    Self::button_style(theme, status, Color::BLACK, Chord::is_note_in_scale(&self, Note::Csharp), is_pressed)
-                                }
-                            })
-                            .width(Length::Fixed(accidental_width))
-                            .height(Length::Fixed(accidental_height))
-                            .padding(5)
-                        )
-                        .on_press(Message::Play(Note::Csharp, true))
-                        .on_release(Message::EndPlaying(Note::Csharp))
-                        .on_exit(Message::EndPlaying(Note::Csharp)),
+                button("+").on_press(Message::OctaveChange(self.octave + 1)),
+                button("-").on_press(Message::OctaveChange(self.octave - 1)),
+                pick_list(
+                    KeyboardRange::ALL,
+                    Some(self.settings.keyboard_range),
+                    Message::KeyboardRangeChange
+                ).width(Length::Fixed(200.0)),
+                text("Zoom:"),
+                slider(0.25..=1.5, self.settings.key_zoom, Message::KeyZoomChange)
+                    .step(0.05)
+                    .on_release(Message::SaveSettings)
+                    .width(Length::Fixed(120.0)),
+                button(text("Keyboard mapping")).on_press(Message::ToggleKeymapEditor),
+            ).spacing(10).padding(5).align_y(alignment::Vertical::Center),
 
-                        Space::with_width(34.0),
-                        MouseArea::new(
-                            button(text(format!("Eb{}\nD#{}", self.octave, self.octave))
-                                .size(24)
-                                .align_x(alignment::Horizontal::Center)
-                                .align_y(alignment::Vertical::Bottom)
-                                .font(Font { weight: Weight::Bold, ..Default::default() })
-                            )
-                            .style({
-                                let buttons = buttons_pressed.clone();
-                                move |theme, status| {
-                                    let is_pressed = *buttons.lock().unwrap().get(&Note::Dsharp).unwrap_or(&false);
-                                    Self::button_style(theme, status, Color::BLACK, Chord::is_note_in_scale(&self, Note::Dsharp), is_pressed)
-                                }
-                            })
-                            .width(Length::Fixed(accidental_width))
-                            .height(Length::Fixed(accidental_height))
-                            .padding(5)
-                        )
-                        .on_press(Message::Play(Note::Dsharp, true))
-                        .on_release(Message::EndPlaying(Note::Dsharp))
-                        .on_exit(Message::EndPlaying(Note::Dsharp)),
-
-                        Space::with_width(93.5),
-                        MouseArea::new(
-                            button(text(format!("Gb{}\nF#{}", self.octave, self.octave))
-                                .size(24)
-                                .align_x(alignment::Horizontal::Center)
-                                .align_y(alignment::Vertical::Bottom)
-                                .font(Font { weight: Weight::Bold, ..Default::default() })
-                            )
-                            .style({
-                                let buttons = buttons_pressed.clone();
-                                move |theme, status| {
-                                    let is_pressed = *buttons.lock().unwrap().get(&Note::Fsharp).unwrap_or(&false);
-                                    Self::button_style(theme, status, Color::BLACK, Chord::is_note_in_scale(&self, Note::Fsharp), is_pressed)
-                                }
-                            })
-                            .width(Length::Fixed(accidental_width))
-                            .height(Length::Fixed(accidental_height))
-                            .padding(5)
-                        )
-                        .on_press(Message::Play(Note::Fsharp, true))
-                        .on_release(Message::EndPlaying(Note::Fsharp))
-                        .on_exit(Message::EndPlaying(Note::Fsharp)),
-
-                        Space::with_width(34.0),
-                        MouseArea::new(
-                            button(text(format!("Ab{}\nG#{}", self.octave, self.octave))
-                                .size(24)
-                                .align_x(alignment::Horizontal::Center)
-                                .align_y(alignment::Vertical::Bottom)
-                                .font(Font { weight: Weight::Bold, ..Default::default() })
-                            )
-                            .style({
-                                let buttons = buttons_pressed.clone();
-                                move |theme, status| {
-                                    let is_pressed = *buttons.lock().unwrap().get(&Note::Gsharp).unwrap_or(&false);
-                                    Self::button_style(theme, status, Color::BLACK, Chord::is_note_in_scale(&self, Note::Gsharp), is_pressed)
-                                }
-                            })
-                            .width(Length::Fixed(accidental_width))
-                            .height(Length::Fixed(accidental_height))
-                            .padding(5)
-                        )
-                        .on_press(Message::Play(Note::Gsharp, true))
-                        .on_release(Message::EndPlaying(Note::Gsharp))
-                        .on_exit(Message::EndPlaying(Note::Gsharp)),
+            container(
+                text(self.sounding_chord().name(&self.spelling()).map_or(String::new(), |name| format!("Chord: {}", name)))
+                    .size(24)
+            ).height(Length::Fixed(50.0)).align_y(alignment::Vertical::Center).padding(5), 
 
-                        Space::with_width(34.0),
-                        MouseArea::new(
-                            button(text(format!("Bb{}\nA#{}", self.octave, self.octave))
-                                .size(24)
-                                .align_x(alignment::Horizontal::Center)
-                                .align_y(alignment::Vertical::Bottom)
-                                .font(Font { weight: Weight::Bold, ..Default::default() })
-                            )
-                            .style({
-                                let buttons = buttons_pressed.clone();
-                                move |theme, status| {
-                                    let is_pressed = *buttons.lock().unwrap().get(&Note::Asharp).unwrap_or(&false);
-                                    Self::button_style(theme, status, Color::BLACK, Chord::is_note_in_scale(&self, Note::Asharp), is_pressed)
-                                }
-                            })
-                            .width(Length::Fixed(accidental_width))
-                            .height(Length::Fixed(accidental_height))
-                            .padding(5)
-                        )
-                        .on_press(Message::Play(Note::Asharp, true))
-                        .on_release(Message::EndPlaying(Note::Asharp))
-                        .on_exit(Message::EndPlaying(Note::Asharp)),
-                    ).spacing(0),
-                ),
-            ),
+            scrollable(self.keyboard(&buttons_pressed))
+                .direction(scrollable::Direction::Horizontal(scrollable::Scrollbar::new())),
             Space::with_height(50), 
 
             widget::column![
@@ -427,7 +231,78 @@ impl Program {
                         button(text("Start recording")).on_press(Message::ToggleRecoring)
                     },
                     text(format!("Time recorded: {:.2}s",  self.time_elapsed)),
-                ).spacing(10) 
+                ).spacing(10),
+                widget::row!(
+                    text_input("Path to a .mid file", &self.import_path)
+                        .on_input(Message::ImportPathChange)
+                        .on_submit(Message::ImportMidi)
+                        .padding(2),
+                    button(text("Import MIDI"))
+                        .on_press_maybe((!self.import_path.trim().is_empty()).then_some(Message::ImportMidi)),
+                ).spacing(10),
+                widget::row!(
+                    text("Last take:"),
+                    if !matches!(self.playback.state, PlaybackState::Playing { .. }) {
+                        button(text("Play")).on_press_maybe(self.last_song.as_ref().map(|_| Message::PlaybackPlay))
+                    } else {
+                        button(text("Pause")).on_press(Message::PlaybackPause)
+                    },
+                    button(text("Stop"))
+                        .on_press_maybe((self.playback.state != PlaybackState::Stopped).then_some(Message::PlaybackStop)),
+                    text(format!("{:.2}s / {:.2}s",
+                        self.playback.position(),
+                        self.last_song.as_ref().map_or(0.0, |song| song.length())
+                    )),
+                ).spacing(10),
+                widget::row!(
+                    text("Key:"),
+                ).spacing(10).extend(self.detected_keys.iter().take(3).map(|(key, fit)| {
+                    button(text(format!("{} ({:.0}%)", key, fit.max(0.0) * 100.0)))
+                        .on_press(Message::UseKey(*key))
+                        .into()
+                })),
+                widget::row!(
+                    text("Transpose:"),
+                    button(text("-12")).on_press_maybe(self.last_song.as_ref().map(|_| Message::TransposeSong(-12))),
+                    button(text("-1")).on_press_maybe(self.last_song.as_ref().map(|_| Message::TransposeSong(-1))),
+                    button(text("+1")).on_press_maybe(self.last_song.as_ref().map(|_| Message::TransposeSong(1))),
+                    button(text("+12")).on_press_maybe(self.last_song.as_ref().map(|_| Message::TransposeSong(12))),
+                    pick_list(
+                        Key::all(),
+                        None::<Key>,
+                        Message::TransposeToKey
+                    ).placeholder("To key").width(Length::Fixed(150.0)),
+                ).spacing(10),
+                widget::row!(
+                    text("Save as:"),
+                    text_input("Path to save the last take to", &self.export_path)
+                        .on_input(Message::ExportPathChange)
+                        .padding(2),
+                ).spacing(10),
+                widget::row!(
+                    button(text("Save MIDI"))
+                        .on_press_maybe(self.last_song.as_ref().map(|_| Message::ExportMidi)),
+                    button(text("Save WAV"))
+                        .on_press_maybe(self.last_song.as_ref().map(|_| Message::ExportWav)),
+                    pick_list(
+                        WavFormat::ALL,
+                        Some(self.wav_format),
+                        Message::WavFormatChange
+                    ).width(Length::Fixed(150.0)),
+                ).spacing(10),
+                widget::row!(
+                    text(self.export_status.clone().unwrap_or_default()),
+                ).spacing(10).push_maybe(self.pending_overwrite.map(|_| widget::row!(
+                    button(text("Overwrite")).on_press(Message::ConfirmOverwrite),
+                    button(text("Cancel")).on_press(Message::CancelOverwrite),
+                ).spacing(10))),
+                widget::row!(
+                    text("Save recordings in:"),
+                    text_input("Folder for new recordings", &self.settings.output_directory.to_string_lossy())
+                        .on_input(Message::OutputDirectoryChange)
+                        .on_submit(Message::SaveSettings)
+                        .padding(2),
+                ).spacing(10),
 
             ].spacing(20).padding(5),
 
@@ -435,13 +310,20 @@ impl Program {
 
             widget::column![
                 widget::row!( 
-                    text("Select Major Scale: "),
+                    text("Select Scale: "),
                     pick_list(
                         Note::ALL,
-                        self.selected_scale.clone(),
+                        self.selected_scale,
                         Message::Scale
-                    ).width(Length::Fixed(150.0)),  
-                )
+                    ).placeholder("None").width(Length::Fixed(150.0)),  
+                    pick_list(
+                        ScaleType::ALL,
+                        Some(self.scale_type),
+                        Message::ScaleTypeChange
+                    ).width(Length::Fixed(200.0)),
+                    button(text("Clear"))
+                        .on_press_maybe(self.selected_scale.map(|_| Message::ClearScale)),
+                ).spacing(10)
             ].spacing(20).padding(5),
 
             Space::with_height(20), 
@@ -456,9 +338,52 @@ impl Program {
                     ),
                     text(format!("{}%",  self.volume)),
                 ).spacing(10),
-            ].spacing(20).padding(5)
+            ].spacing(20).padding(5),
+
+            widget::column![
+                widget::row!(
+                    text("Waveform:"),
+                    pick_list(
+                        Waveform::ALL,
+                        Some(self.settings.instrument.waveform),
+                        Message::WaveformChange
+                    ).width(Length::Fixed(170.0)),
+                ).spacing(10).push_maybe(
+                    (self.settings.instrument.waveform == Waveform::Pulse).then(|| widget::row!(
+                        text("Pulse width:"),
+                        slider(0.05..=0.95, self.settings.instrument.pulse_width, Message::PulseWidthChange)
+                            .step(0.01)
+                            .on_release(Message::SaveSettings),
+                        text(format!("{:.0}%", self.settings.instrument.pulse_width * 100.0)),
+                    ).spacing(10))
+                ),
+                widget::row!(
+                    text("Attack:"),
+                    slider(0.0..=2.0, self.settings.instrument.envelope.attack, Message::AttackChange)
+                        .step(0.01)
+                        .on_release(Message::SaveSettings),
+                    text(format!("{:.2}s", self.settings.instrument.envelope.attack)),
+                    text("Decay:"),
+                    slider(0.0..=2.0, self.settings.instrument.envelope.decay, Message::DecayChange)
+                        .step(0.01)
+                        .on_release(Message::SaveSettings),
+                    text(format!("{:.2}s", self.settings.instrument.envelope.decay)),
+                ).spacing(10),
+                widget::row!(
+                    text("Sustain:"),
+                    slider(0.0..=1.0, self.settings.instrument.envelope.sustain, Message::SustainChange)
+                        .step(0.01)
+                        .on_release(Message::SaveSettings),
+                    text(format!("{:.0}%", self.settings.instrument.envelope.sustain * 100.0)),
+                    text("Release:"),
+                    slider(0.0..=3.0, self.settings.instrument.envelope.release, Message::ReleaseChange)
+                        .step(0.01)
+                        .on_release(Message::SaveSettings),
+                    text(format!("{:.2}s", self.settings.instrument.envelope.release)),
+                ).spacing(10),
+            ].spacing(10).padding(5)
             
             ]
-        ).into()
+        ))
     }
 }
\ No newline at end of file
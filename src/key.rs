@@ -0,0 +1,142 @@
+// use other files inside this project
+use crate::{Note, Song};
+use crate::scale::ScaleType;
+use crate::spelling::Spelling;
+
+// use dependencies
+use std::fmt;
+
+// The Krumhansl-Kessler key profiles, how strongly each note of the
+// chromatic scale (counting up from the tonic) belongs to a major or minor key
+const MAJOR_PROFILE: [f32; 12] = [6.35, 2.23, 3.48, 2.33, 4.38, 4.09, 2.52, 5.19, 2.39, 3.66, 2.29, 2.88];
+const MINOR_PROFILE: [f32; 12] = [6.33, 2.68, 3.52, 5.38, 2.60, 3.53, 2.54, 4.75, 3.98, 2.69, 3.34, 3.17];
+
+// Key struct, a tonic and the scale built on it
+// fields:
+// 1. root       -> The tonic of the key
+// 2. scale_type -> Major or natural minor
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Key {
+    pub root: Note,
+    pub scale_type: ScaleType,
+}
+
+// allows Key to be converted to String, such as "F# minor" or "Bb major"
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let root = Spelling::for_key(self.root, self.scale_type).name(self.root);
+        match self.scale_type {
+            ScaleType::Major => write!(f, "{} major", root),
+            ScaleType::NaturalMinor => write!(f, "{} minor", root),
+            scale_type => write!(f, "{} {}", root, scale_type),
+        }
+    }
+}
+
+// implement the Key struct
+// functions:
+// 1. all         -> Every major and minor key
+// 2. detect      -> Every major and minor key, ordered from the most to the least
+//                   likely key of the song, with how well the song fits it (-1.0 to 1.0)
+// 3. transposed  -> The key moved by the given number of semitones
+// 4. from_signature -> The major or minor key with the given sharps (positive)
+//                      or flats (negative) in its key signature
+// 5. correlation -> The Pearson correlation of two sets of values
+impl Key {
+    pub fn all() -> Vec<Key> {
+        Note::ALL.iter()
+            .flat_map(|root| [ScaleType::Major, ScaleType::NaturalMinor].map(|scale_type| Key { root: *root, scale_type }))
+            .collect()
+    }
+
+    pub fn detect(song: &Song) -> Vec<(Key, f32)> {
+        // how long each note of the chromatic scale is played for in total
+        let mut durations = [0.0; 12];
+        for (pitch, _, duration, _) in &song.notes {
+            durations[pitch.note().index()] += duration;
+        }
+        if durations.iter().all(|duration| *duration == 0.0) {
+            return Vec::new();
+        }
+
+        let mut keys: Vec<(Key, f32)> = Self::all().into_iter()
+            .map(|key| {
+                let tonic = key.root.index();
+                let profile = if key.scale_type == ScaleType::Major { MAJOR_PROFILE } else { MINOR_PROFILE };
+                // line the profile up so that it starts on this tonic
+                let profile: Vec<f32> = (0..12).map(|index| profile[(index + 12 - tonic) % 12]).collect();
+                (key, Self::correlation(&durations, &profile))
+            })
+            .collect();
+        keys.sort_by(|a, b| b.1.total_cmp(&a.1));
+        keys
+    }
+
+    pub fn transposed(&self, semitones: i32) -> Key {
+        Key {
+            root: Note::from_index((self.root.index() as i32 + semitones).rem_euclid(12) as usize),
+            scale_type: self.scale_type,
+        }
+    }
+
+    pub fn from_signature(sharps: i8, minor: bool) -> Key {
+        // each sharp moves the major key up a 5th, and its relative minor is a minor 3rd below
+        let major_root = (sharps as i32 * 7).rem_euclid(12) as usize;
+        if minor {
+            Key { root: Note::from_index(major_root + 9), scale_type: ScaleType::NaturalMinor }
+        } else {
+            Key { root: Note::from_index(major_root), scale_type: ScaleType::Major }
+        }
+    }
+
+    fn correlation(a: &[f32], b: &[f32]) -> f32 {
+        let mean = |values: &[f32]| values.iter().sum::<f32>() / values.len() as f32;
+        let (mean_a, mean_b) = (mean(a), mean(b));
+
+        let covariance: f32 = a.iter().zip(b).map(|(a, b)| (a - mean_a) * (b - mean_b)).sum();
+        let spread_a: f32 = a.iter().map(|a| (a - mean_a).powi(2)).sum();
+        let spread_b: f32 = b.iter().map(|b| (b - mean_b).powi(2)).sum();
+
+        // every note played for as long as each other fits no key
+        if spread_a == 0.0 || spread_b == 0.0 {
+            return 0.0;
+        }
+        covariance / (spread_a * spread_b).sqrt()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::song_of;
+
+    #[test]
+    fn detects_the_key_of_a_scale_with_a_held_tonic() {
+        let song = song_of(&[
+            (Note::G, 4, 2.0), (Note::A, 4, 0.5), (Note::B, 4, 1.0), (Note::C, 4, 0.5),
+            (Note::D, 4, 1.5), (Note::E, 4, 0.5), (Note::Fsharp, 4, 0.5), (Note::G, 4, 2.0),
+        ]);
+        let keys = Key::detect(&song);
+
+        assert_eq!(keys.len(), 24);
+        assert_eq!(keys[0].0, Key { root: Note::G, scale_type: ScaleType::Major });
+        assert!(keys[0].1 > keys[1].1);
+    }
+
+    #[test]
+    fn detects_minor_keys() {
+        let song = song_of(&[
+            (Note::A, 4, 2.0), (Note::C, 4, 1.0), (Note::E, 4, 1.5), (Note::Gsharp, 4, 0.5),
+            (Note::B, 4, 0.5), (Note::D, 4, 0.5), (Note::F, 4, 0.5), (Note::A, 4, 2.0),
+        ]);
+
+        assert_eq!(Key::detect(&song)[0].0, Key { root: Note::A, scale_type: ScaleType::NaturalMinor });
+    }
+
+    #[test]
+    fn an_empty_song_has_no_key() {
+        assert!(Key::detect(&Song::default()).is_empty());
+        assert_eq!(Key { root: Note::Fsharp, scale_type: ScaleType::NaturalMinor }.to_string(), "F# minor");
+        assert_eq!(Key { root: Note::Asharp, scale_type: ScaleType::Major }.to_string(), "Bb major");
+    }
+}
@@ -0,0 +1,292 @@
+// use other files inside this project
+use crate::Note;
+
+// use dependencies
+use iced::keyboard;
+use serde_json::{json, Value};
+use std::fmt;
+use strum_macros::Display;
+
+// The highest number of semitones above the current octave's C a key can play
+pub const HIGHEST_SEMITONES: i32 = 35;
+// The highest octave the computer keyboard can play from, so its highest key stays a midi key
+pub const HIGHEST_OCTAVE: i32 = 6;
+
+// KeymapPreset enum defines the bundled layouts of computer keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum KeymapPreset {
+    #[strum(to_string = "Ableton (QWERTY)")]
+    Ableton,
+    #[strum(to_string = "Tracker (two rows)")]
+    Tracker,
+    #[strum(to_string = "AZERTY")]
+    Azerty,
+    #[strum(to_string = "Dvorak")]
+    Dvorak,
+}
+
+// implement the KeymapPreset enum
+// functions:
+// 1. keymap -> The keymap of the preset
+impl KeymapPreset {
+    pub const ALL: [KeymapPreset; 4] = [
+        KeymapPreset::Ableton, KeymapPreset::Tracker, KeymapPreset::Azerty, KeymapPreset::Dvorak
+    ];
+
+    pub fn keymap(&self) -> Keymap {
+        // (rows, octave down, octave up), each row being the semitones above the C of the
+        // current octave its first key plays and its keys from there up
+        let (rows, octave_down, octave_up): (&[(i32, &[&str])], &str, &str) = match self {
+            // the home row plays the white keys and the row above the black keys
+            KeymapPreset::Ableton => (&[(0, &["a", "w", "s", "e", "d", "f", "t", "g", "y", "h", "u", "j", "k", "o", "l", "p", ";"])], "z", "x"),
+            // the bottom row plays the current octave and the top row the octave above,
+            // each running on to the E above so the rows overlap
+            KeymapPreset::Tracker => (&[
+                (0, &["z", "s", "x", "d", "c", "v", "g", "b", "h", "n", "j", "m", ",", "l", ".", ";", "/"]),
+                (12, &["q", "2", "w", "3", "e", "r", "5", "t", "6", "y", "7", "u", "i", "9", "o", "0", "p"]),
+            ], "-", "="),
+            // the keys in the same places as the Ableton layout on other keyboards
+            KeymapPreset::Azerty => (&[(0, &["q", "z", "s", "e", "d", "f", "t", "g", "y", "h", "u", "j", "k", "o", "l", "p", "m"])], "w", "x"),
+            KeymapPreset::Dvorak => (&[(0, &["a", ",", "o", ".", "e", "u", "y", "i", "f", "d", "g", "h", "t", "r", "n", "l", "s"])], ";", "q"),
+        };
+
+        Keymap {
+            name: self.to_string(),
+            notes: rows.iter()
+                .flat_map(|(lowest, keys)| keys.iter()
+                    .enumerate()
+                    .map(move |(semitones, key)| (key.to_string(), lowest + semitones as i32)))
+                .collect(),
+            octave_down: octave_down.to_string(),
+            octave_up: octave_up.to_string(),
+            sustain: "Space".to_string(),
+        }
+    }
+}
+
+// KeymapControl enum defines the keys of a keymap which do not play notes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum KeymapControl {
+    #[strum(to_string = "Octave down")]
+    OctaveDown,
+    #[strum(to_string = "Octave up")]
+    OctaveUp,
+    #[strum(to_string = "Sustain pedal")]
+    Sustain,
+}
+
+impl KeymapControl {
+    pub const ALL: [KeymapControl; 3] = [KeymapControl::OctaveDown, KeymapControl::OctaveUp, KeymapControl::Sustain];
+}
+
+// KeymapNote struct, the note a key plays as the semitones above the
+// C of the current octave, shown such as "E (+1 octave)"
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeymapNote(pub i32);
+
+impl KeymapNote {
+    pub fn all() -> Vec<KeymapNote> {
+        (0..=HIGHEST_SEMITONES).map(KeymapNote).collect()
+    }
+}
+
+impl fmt::Display for KeymapNote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let note = Note::from_index(self.0 as usize);
+        match self.0 / 12 {
+            0 => write!(f, "{}", note),
+            1 => write!(f, "{} (+1 octave)", note),
+            octaves => write!(f, "{} (+{} octaves)", note, octaves),
+        }
+    }
+}
+
+// Keymap struct, which computer keys play notes and which change the octave
+// or act as a sustain pedal, each key written as its character such as "a",
+// or its name such as "Space"
+// fields:
+// 1. name        -> The name of the preset the keymap came from, or "Custom"
+// 2. notes       -> Each key with the semitones above the C of the current octave it plays
+// 3. octave_down -> The key which moves down an octave
+// 4. octave_up   -> The key which moves up an octave
+// 5. sustain     -> The key held down as a sustain pedal
+#[derive(Debug, Clone, PartialEq)]
+pub struct Keymap {
+    pub name: String,
+    pub notes: Vec<(String, i32)>,
+    pub octave_down: String,
+    pub octave_up: String,
+    pub sustain: String,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        KeymapPreset::Ableton.keymap()
+    }
+}
+
+// implement the Keymap struct
+// functions:
+// 1. key_name  -> How a key pressed on the computer keyboard is written in a keymap
+//    is_key    -> Whether a key written in a keymap is the given key, ignoring case
+//                 so "A", "space" and "SPACE" are written as the user likes
+// 2. semitones -> The semitones above the current octave's C the key plays, if any
+// 3. control   -> The key of one of the keys which do not play notes
+//    set_control -> Changes one of the keys which do not play notes
+// 4. from_json -> Reads a keymap out of a json value
+// 5. to_json   -> Converts the keymap into a json value
+impl Keymap {
+    pub fn key_name(key: &keyboard::Key) -> Option<String> {
+        match key {
+            keyboard::Key::Character(c) => Some(c.to_lowercase()),
+            keyboard::Key::Named(named) => Some(format!("{:?}", named)),
+            keyboard::Key::Unidentified => None,
+        }
+    }
+
+    pub fn is_key(written: &str, key_name: &str) -> bool {
+        written.to_lowercase() == key_name.to_lowercase()
+    }
+
+    pub fn semitones(&self, key: &str) -> Option<i32> {
+        self.notes.iter()
+            .find(|(note_key, _)| Self::is_key(note_key, key))
+            .map(|(_, semitones)| *semitones)
+    }
+
+    pub fn control(&self, control: KeymapControl) -> &str {
+        match control {
+            KeymapControl::OctaveDown => &self.octave_down,
+            KeymapControl::OctaveUp => &self.octave_up,
+            KeymapControl::Sustain => &self.sustain,
+        }
+    }
+
+    pub fn set_control(&mut self, control: KeymapControl, key: String) {
+        match control {
+            KeymapControl::OctaveDown => self.octave_down = key,
+            KeymapControl::OctaveUp => self.octave_up = key,
+            KeymapControl::Sustain => self.sustain = key,
+        }
+    }
+
+    pub fn from_json(keymap_json: &Value) -> Option<Keymap> {
+        let defaults = Self::default();
+        let key = |name: &str, default: String| {
+            keymap_json.get(name)
+                .and_then(Value::as_str)
+                .map_or(default, str::to_string)
+        };
+
+        // each note is a [key, semitones] pair, so keys left empty or
+        // written twice in the editor are kept as they are
+        let notes: Vec<(String, i32)> = keymap_json.get("notes")?
+            .as_array()?
+            .iter()
+            .filter_map(|note| Some((note.get(0)?.as_str()?.to_string(), note.get(1)?.as_i64()? as i32)))
+            .filter(|(_, semitones)| (0..=HIGHEST_SEMITONES).contains(semitones))
+            .collect();
+
+        Some(Keymap {
+            name: key("name", "Custom".to_string()),
+            notes,
+            octave_down: key("octave_down", defaults.octave_down),
+            octave_up: key("octave_up", defaults.octave_up),
+            sustain: key("sustain", defaults.sustain),
+        })
+    }
+
+    pub fn to_json(&self) -> Value {
+        let notes: Vec<Value> = self.notes.iter()
+            .map(|(key, semitones)| json!([key, semitones]))
+            .collect();
+
+        json!({
+            "name": self.name,
+            "notes": notes,
+            "octave_down": self.octave_down,
+            "octave_up": self.octave_up,
+            "sustain": self.sustain,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_play_each_note_from_one_key() {
+        for preset in KeymapPreset::ALL {
+            let keymap = preset.keymap();
+            for (index, (key, semitones)) in keymap.notes.iter().enumerate() {
+                if preset != KeymapPreset::Tracker {
+                    assert_eq!(*semitones, index as i32, "{}", preset);
+                }
+                assert_eq!(keymap.notes.iter().filter(|(other, _)| other == key).count(), 1, "{} {}", preset, key);
+                assert!(KeymapControl::ALL.iter().all(|control| keymap.control(*control) != key), "{} {}", preset, key);
+            }
+        }
+
+        // the Ableton layout plays B before the C above it
+        let ableton = KeymapPreset::Ableton.keymap();
+        assert_eq!((ableton.semitones("j"), ableton.semitones("k")), (Some(11), Some(12)));
+    }
+
+    #[test]
+    fn tracker_rows_play_an_octave_apart() {
+        let tracker = KeymapPreset::Tracker.keymap();
+        let row = |keys: &[&str]| keys.iter().map(|key| tracker.semitones(key).unwrap()).collect::<Vec<i32>>();
+
+        assert_eq!(row(&["z", "s", "x", "m"]), [0, 1, 2, 11]);
+        assert_eq!(row(&["q", "2", "w", "u"]), [12, 13, 14, 23]);
+        // the end of the bottom row plays the same notes as the start of the top row
+        assert_eq!(row(&[",", "l", "/"]), row(&["q", "2", "e"]));
+        assert_eq!(row(&["i", "p"]), [24, 28]);
+    }
+
+    #[test]
+    fn keymaps_read_back_from_json() {
+        let mut keymap = KeymapPreset::Dvorak.keymap();
+        keymap.set_control(KeymapControl::Sustain, "Shift".to_string());
+        assert_eq!(Keymap::from_json(&keymap.to_json()), Some(keymap.clone()));
+
+        // rows added in the editor and not filled in yet, or keys written twice, are saved too
+        keymap.notes.push((String::new(), 3));
+        keymap.notes.push((String::new(), 4));
+        keymap.notes.push(("a".to_string(), 5));
+        assert_eq!(Keymap::from_json(&keymap.to_json()), Some(keymap));
+
+        let custom = Keymap::from_json(&json!({ "notes": [["j", 0], ["Tab", 1], ["x", 99], ["K", 2], [3, 4]] })).unwrap();
+        assert_eq!(custom.name, "Custom");
+        assert_eq!(custom.notes, vec![("j".to_string(), 0), ("Tab".to_string(), 1), ("K".to_string(), 2)]);
+        assert_eq!(custom.semitones("k"), Some(2));
+        assert_eq!(custom.octave_up, Keymap::default().octave_up);
+        assert_eq!(Keymap::from_json(&json!({ "name": "No notes" })), None);
+    }
+
+    #[test]
+    fn pressed_keys_are_named_like_the_keymap() {
+        assert_eq!(Keymap::key_name(&keyboard::Key::Character("A".into())), Some("a".to_string()));
+        assert_eq!(Keymap::key_name(&keyboard::Key::Named(keyboard::key::Named::Space)), Some("Space".to_string()));
+        assert_eq!(KeymapNote(16).to_string(), "E (+1 octave)");
+    }
+
+    #[test]
+    fn named_keys_typed_into_the_editor_match_when_pressed() {
+        let mut keymap = Keymap::default();
+        // the editor sends the whole text after each character typed
+        let mut typed = String::new();
+        for c in "Space".chars() {
+            typed.push(c);
+            keymap.set_control(KeymapControl::Sustain, typed.clone());
+            keymap.notes[0].0 = typed.clone();
+        }
+        assert_eq!(keymap.sustain, "Space");
+
+        let space = Keymap::key_name(&keyboard::Key::Named(keyboard::key::Named::Space)).unwrap();
+        assert!(Keymap::is_key(keymap.control(KeymapControl::Sustain), &space));
+        assert_eq!(keymap.semitones(&space), Some(0));
+        assert!(Keymap::is_key("A", "a") && Keymap::is_key("arrowup", "ArrowUp"));
+    }
+}
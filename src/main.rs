@@ -1,94 +1,82 @@
 #![windows_subsystem = "windows"]
 // use other files inside this project
 mod gui;
+mod audio;
 mod chord;
+mod export;
+mod key;
+mod keymap;
 mod midi;
 mod note;
+mod piano;
+mod playback;
+mod render;
+mod scale;
+mod settings;
+mod song;
+mod spelling;
+mod synth;
+#[cfg(test)]
+mod test_helpers;
 use gui::{*};
 use chord::{*};
 use note::{*};
+use audio::{AudioEngine, SoundRequest, VoiceId};
+use export::ExportKind;
+use key::Key;
+use keymap::{Keymap, KeymapControl, KeymapNote, KeymapPreset, HIGHEST_OCTAVE};
+use piano::{KeyboardRange, Piano};
+use playback::{Playback, PlaybackState};
+use render::{Render, WavFormat};
+use scale::ScaleType;
+use settings::Settings;
+use song::Song;
+use spelling::Spelling;
+use synth::Waveform;
 
 
 // use dependencies     
 use iced::{keyboard::{self}, Element, Size, Subscription, Theme};
 use once_cell::sync::Lazy;
-use rodio::{self, OutputStream, OutputStreamHandle, Sink, Source};
-use std::{fs, io::Read};
-use std::{thread, collections::HashMap, fs::File,  sync::{Arc, Mutex}, time::Duration};
+use std::io::Read;
+use std::{collections::HashMap, fs::File,  sync::{Arc, Mutex}, time::Instant};
 use iced::futures::{self, Stream};
 use std::pin::Pin;
 use std::task::{Context, Poll};
 use futures::stream::StreamExt;
 use iced_native::subscription::Recipe;
-use serde_json;
-
-#[derive(Clone)]
-struct SoundRequest {
-    frequency: u32,
-    duration: f32,
-    volume: f32,
-    real_note: RealNote,
-    bpm: f32
-}
 
 // playable trait to implement polymorphism
-// for structs RealNote and Chordf
+// for structs RealNote and Chord
 trait Playable {
-    fn play(&self, bpm: f32, is_recording: bool, volume: f32);
+    fn play(&self, engine: &AudioEngine, bpm: f32, is_recording: bool, volume: f32);
+    fn start(&self, engine: &AudioEngine, volume: f32) -> Vec<VoiceId>;
 }
 
 // Mutually exclusive, thread-safe static variables for storing important 
 // information which needs to be used throughout the program
-static RECORDED_NOTES: Lazy<Arc<Mutex<HashMap<Note, Vec<(f32, f32, f32)>>>>> = Lazy::new(|| {
+type RecordedNotes = HashMap<Pitch, Vec<(f32, f32, u8)>>; // Pitch -> (start_time, duration, velocity)
+type HeldNotes = HashMap<Pitch, (Vec<(RealNote, VoiceId)>, Instant, u8)>; // Pitch -> (notes and their voices, pressed_at, velocity)
+type PlayedChords = HashMap<Pitch, Vec<RealNote>>; // Pitch -> the notes played by pressing it
+
+static RECORDED_NOTES: Lazy<Arc<Mutex<RecordedNotes>>> = Lazy::new(|| {
     Arc::new(Mutex::new(HashMap::new()))
 });
 static RECORDING_START_TIME: Lazy<Arc<Mutex<Option<std::time::Instant>>>> = Lazy::new(|| {
     Arc::new(Mutex::new(None))
 });
-const THREAD_POOL: Lazy<Arc<Mutex<rayon::ThreadPool>>> = Lazy::new(|| {
-    Arc::new(Mutex::new(rayon::ThreadPoolBuilder::new().num_threads(4).build().unwrap()))
-});
 
 
 
 
-#[derive(Debug, Clone)]
-struct Song {
-    notes: Vec<(Note, f32, f32, f32)>, // Note, octave, start_time, duration
-    bpm: f32,
-}
-
-impl Default for Song { 
-    fn default() -> Self {
-        Self {
-            bpm: 120.0,
-            notes: vec![]
-        }
-    }
-}
-
-pub fn async_play_note(notes: &[RealNote], bpm: f32, is_recording: bool, volume: f32) {
-    for note in notes {
-        let note = note.clone();
-        //THREAD_POOL.lock().unwrap().execute(move || note.play_sound(bpm, is_recording, sink));
-
-        THREAD_POOL.lock().unwrap().spawn(move || {
-            note.play(bpm, is_recording, volume);
-        });
-        // thread::spawn(move || {
-        //     note.play(bpm, is_recording, volume);
-        // });
-    }
-}
-
-pub fn record_history(real_note: RealNote, time: f32) { 
+pub fn record_history(real_note: RealNote, pressed_at: Instant, time: f32, velocity: u8) { 
     let recording_start_guard = RECORDING_START_TIME.lock().unwrap();
     if let Some(start_time) = &*recording_start_guard {
-        let elapsed = start_time.elapsed().as_secs_f32();
+        let elapsed = pressed_at.saturating_duration_since(*start_time).as_secs_f32();
         let mut recorded_notes = RECORDED_NOTES.lock().unwrap();
-        recorded_notes.entry(real_note.note.clone())
-            .or_insert_with(Vec::new)
-            .push((real_note.octave, elapsed, time)); // (octave, start_time, duration)
+        recorded_notes.entry(real_note.pitch)
+            .or_default()
+            .push((elapsed, time, velocity)); // (start_time, duration, velocity)
     }
 }
 
@@ -96,50 +84,128 @@ pub fn record_history(real_note: RealNote, time: f32) {
 #[derive(Debug, Clone, PartialEq)]
 enum Message { 
     Scale(Note), 
-    OctaveChange(f32),
+    ScaleTypeChange(ScaleType),
+    ClearScale,
+    OctaveChange(i32),
+    KeyboardRangeChange(KeyboardRange),
+    KeyZoomChange(f32),
     BpmChange(f32),
     CustomBpmChange(String),
-    Play(Note, bool), // True if played with gui
-    EndPlaying(Note),
+    Play(Pitch, bool), // True if played with gui
+    EndPlaying(Pitch),
     KeyPressed(iced::keyboard::Key),
     KeyReleased(iced::keyboard::Key),
-    PlayChords,
-    PlayAsync,
+    SustainPedal(bool),
+    ChordModeChange(ChordMode),
+    ChordQualityChange(ChordQuality),
+    InversionChange(Inversion),
+    VoicingChange(Voicing),
+    HoldNotes,
     ToggleRecoring,
     NoteLengthChange(f32),
     VolumeChange(f32),
+    AttackChange(f32),
+    DecayChange(f32),
+    SustainChange(f32),
+    ReleaseChange(f32),
+    WaveformChange(Waveform),
+    PulseWidthChange(f32),
+    SaveSettings,
+    WavFormatChange(WavFormat),
+    ExportPathChange(String),
+    OutputDirectoryChange(String),
+    ExportMidi,
+    ExportWav,
+    ConfirmOverwrite,
+    CancelOverwrite,
+    ImportPathChange(String),
+    ImportMidi,
+    PlaybackPlay,
+    PlaybackPause,
+    PlaybackStop,
+    UseKey(Key),
+    TransposeSong(i32),
+    TransposeToKey(Key),
     ToggleHelpGUI,
+    ToggleKeymapEditor,
+    KeymapPresetChange(KeymapPreset),
+    KeymapKeyChange(usize, String),
+    KeymapNoteChange(usize, KeymapNote),
+    KeymapControlChange(KeymapControl, String),
+    AddKeymapKey,
+    RemoveKeymapKey(usize),
     Tick
 }
 
 #[derive(Clone)]
 // Program struct, which stores the current information the program may need
 // fields:
-// 1. octave           -> The current octave the program is using
+// 1. octave           -> The octave the computer keyboard plays in, which the keyboard on screen
+//                        starts from as near as its range allows
 // 2. bpm              -> The current beats per minute the program is using
 // 3. custom_bpm       -> String representation of the bpm, required for iced
-// 4. play_chords      -> Whether or not the play triad button is selected
-// 5. play_async       -> Whether or not to play notes asynchronously 
+// 4. chord_mode       -> What is played when a single key is pressed
+//    chord_quality    -> The kind of chord played in ChordMode::Quality
+//    inversion        -> The inversion chords are played in
+//    voicing          -> How the notes of chords are spread across octaves
+// 5. hold_notes       -> Whether notes sound for as long as they are held
+//                        instead of for the note length
 // 6. is_recording     -> Whether or not the program is currently recording
-// 7. selected_scale   -> The scale that the program is currently using
+// 7. selected_scale   -> The root of the scale that the program is currently using
+//    scale_type       -> The kind of scale built on selected_scale
 // 8. time_elapsed     -> The time elapsed since recording started
 // 9. note_length      -> The length of the note
 // 10. volume          -> The volume of the note
-// 11. buttons_pressed -> The buttons that are currently pressed
+// 11. buttons_pressed -> The keys that are currently pressed
+//     keyboard_keys   -> The key each held computer key is playing (None for the octave
+//                        and sustain pedal keys), so it is released even if the octave changes
+//     sustain_pedal   -> Whether the sustain pedal is down, keeping notes sounding
+//                        after their key is let go of
+// 12. held_notes      -> The notes sounding for each key with the voices playing them, until it is
+//                        let go of or until the sustain pedal is, when it was pressed and the
+//                        velocity it was pressed with
+//     played_chords   -> The notes played for their note length by each pressed key
+// 13. audio_engine    -> The handle used to send notes to the audio thread
+// 14. settings        -> The settings saved between runs, such as the envelope
+// 15. last_song       -> The song from the last recording or import
+// 16. playback        -> The transport playing last_song
+// 17. wav_format      -> The sample format used when exporting a wav file
+// 18. import_path     -> The path of the midi file to import
+// 19. export_path     -> The path last_song is exported to
+// 20. pending_overwrite -> The export waiting for the user to confirm overwriting a file
+// 21. export_status   -> The result of the last import/export, shown to the user
+// 22. detected_keys   -> The likeliest keys of last_song, with how well it fits each
 struct Program { 
-    octave: f32,
+    octave: i32,
     bpm: f32,
     custom_bpm: String,
-    play_chords: bool,
-    play_async: bool,
+    chord_mode: ChordMode,
+    chord_quality: ChordQuality,
+    inversion: Inversion,
+    voicing: Voicing,
+    hold_notes: bool,
     is_recording: bool,
     selected_scale: Option<Note>,  
+    scale_type: ScaleType,
     time_elapsed: f32,
     note_length: f32,
     volume: f32,
-    buttons_pressed: HashMap<Note, bool>,
-    sound_channel: Arc<Mutex<(std::sync::mpsc::Sender<SoundRequest>, std::sync::mpsc::Receiver<SoundRequest>)>>,
-    current_menu: CurrentMenu
+    buttons_pressed: HashMap<Pitch, bool>,
+    keyboard_keys: HashMap<keyboard::Key, Option<Pitch>>,
+    sustain_pedal: bool,
+    held_notes: HeldNotes,
+    played_chords: PlayedChords,
+    audio_engine: AudioEngine,
+    settings: Settings,
+    current_menu: CurrentMenu,
+    last_song: Option<Song>,
+    playback: Playback,
+    wav_format: WavFormat,
+    import_path: String,
+    export_path: String,
+    pending_overwrite: Option<ExportKind>,
+    export_status: Option<String>,
+    detected_keys: Vec<(Key, f32)>,
 }
 
 // implement the Program struct
@@ -151,17 +217,58 @@ struct Program {
 // 5. start_recording -> begin recording midi file
 // 6. stop_recording  -> stop recording midi
 // 7. get_note_length -> get the NoteLength from a float
-// 8. match_keyboard_key -> match the keyboard key to a Note
+// 8. match_keyboard_key -> the pitch a computer key plays in the current octave
+// 9. release_note    -> stop the notes started by a key, recording how long they sounded
+//    end_sustain     -> stop the notes whose keys were let go of while the sustain pedal was down
+// 10. save_settings  -> write the current settings to settings.json
+// 11. update_instrument -> send the instrument settings to the audio engine
+// 12. export_song    -> write last_song to export_path, asking before overwriting a file
+// 13. sounding_chord -> every note currently held down, pressed or played back
+// 14. chord_of       -> the chord made by the held, pressed and played back notes
+// 15. set_last_song  -> replace last_song, stopping its playback and detecting its key
+// 16. spelling       -> how notes are written in the selected scale
 impl Program { 
+    pub fn sounding_chord(&self) -> Chord {
+        Self::chord_of(&self.held_notes, &self.played_chords, &self.playback)
+    }
+
+    fn chord_of(held_notes: &HeldNotes, played_chords: &PlayedChords, playback: &Playback) -> Chord {
+        let mut notes: Vec<RealNote> = held_notes.values()
+            .flat_map(|(notes, _, _)| notes.iter().map(|(note, _)| note.clone()))
+            .collect();
+        notes.extend(played_chords.values().flatten().cloned());
+        notes.extend(playback.sounding_pitches()
+            .map(|pitch| RealNote { pitch, length: NoteLength::Quarter }));
+
+        Chord { notes }
+    }
+
+    pub fn set_last_song(&mut self, mut song: Song) {
+        self.playback.stop(&self.audio_engine);
+        self.detected_keys = Key::detect(&song);
+        // a key signature read from a midi file is kept over the detected key
+        if song.key.is_none() {
+            song.key = self.detected_keys.first().map(|(key, _)| *key);
+        }
+        self.last_song = Some(song);
+    }
+
+    pub fn spelling(&self) -> Spelling {
+        match self.selected_scale {
+            Some(root) => Spelling::for_key(root, self.scale_type),
+            None => Spelling::default(),
+        }
+    }
+
     pub fn get_note_length(length: f32) -> NoteLength { 
-        return match length {
+        match length {
             5.0 => NoteLength::Whole,
             4.0 => NoteLength::Half,
             3.0 => NoteLength::Quarter,
             2.0 => NoteLength::Eighth,
             1.0 => NoteLength::Sixteenth,
             _ =>  NoteLength::Whole
-        }; 
+        }
     }
 
     pub fn start_recording(&mut self) {
@@ -177,16 +284,76 @@ impl Program {
         let mut song = Song {
             notes: vec![],
             bpm: self.bpm,
+            key: None,
         };
     
-        for (note, data) in recorded_notes {
-            for (octave, start_time, duration) in data {
-                song.notes.push((note.clone(), octave, start_time, duration));
+        for (pitch, data) in recorded_notes {
+            for (start_time, duration, velocity) in data {
+                song.notes.push((pitch, start_time, duration, velocity));
             }
         }
+        song.notes.sort_by(|a, b| a.1.total_cmp(&b.1));
         song
     }
     
+    pub fn release_note(&mut self, pitch: Pitch) {
+        if let Some((notes, pressed_at, velocity)) = self.held_notes.remove(&pitch) {
+            let time = pressed_at.elapsed().as_secs_f32();
+            for (real_note, voice) in notes {
+                self.audio_engine.send(SoundRequest::NoteOff { id: voice });
+                if self.is_recording {
+                    record_history(real_note, pressed_at, time, velocity);
+                }
+            }
+        }
+    }
+
+    pub fn end_sustain(&mut self) {
+        let released: Vec<Pitch> = self.held_notes.keys()
+            .filter(|pitch| self.buttons_pressed.get(pitch) != Some(&true))
+            .copied()
+            .collect();
+        for pitch in released {
+            self.release_note(pitch);
+        }
+    }
+
+    pub fn save_settings(&self) {
+        if let Err(e) = self.settings.save() {
+            println!("An error occured saving settings: {}", e);
+        }
+    }
+
+    pub fn update_instrument(&self) {
+        self.audio_engine.send(SoundRequest::SetInstrument(self.settings.instrument));
+    }
+
+    pub fn export_song(&mut self, kind: ExportKind, overwrite: bool) {
+        let Some(song) = &self.last_song else {
+            return;
+        };
+        let path = kind.with_extension(std::path::Path::new(self.export_path.trim()));
+
+        if !overwrite && path.exists() {
+            self.pending_overwrite = Some(kind);
+            self.export_status = Some(format!("{} already exists. Overwrite it?", path.display()));
+            return;
+        }
+        self.pending_overwrite = None;
+
+        let result = match kind {
+            ExportKind::Midi => midi::Midi::midi_file_create(song, &path)
+                .map_err(|e| e.to_string()),
+            ExportKind::Wav => Render::wav_file_create(song, self.settings.instrument, self.wav_format, &path)
+                .map_err(|e| format!("could not write wav file: {}", e)),
+        };
+
+        self.export_status = match result {
+            Ok(()) => Some(format!("Saved to {}", path.display())),
+            Err(e) => Some(format!("{} export failed: {}", kind, e)),
+        };
+    }
+
     pub fn update_bpm(&mut self, value: f32) {
         if NoteLength::check_bpm(value) {
             self.bpm = value;
@@ -197,35 +364,93 @@ impl Program {
         }
     }
 
-    fn view(&self) -> Element<Message> {
-        Self::get_ui_information(self, Arc::new(Mutex::new(self.buttons_pressed.clone()))).into()
+    fn view(&self) -> Element<'_, Message> {
+        let mut buttons_pressed = self.buttons_pressed.clone();
+        // keys held by the sustain pedal are shown down until their notes end
+        for pitch in self.playback.sounding_pitches().chain(self.held_notes.keys().copied()) {
+            buttons_pressed.insert(pitch, true);
+        }
+        Self::get_ui_information(self, buttons_pressed).into()
     }
 
-    fn match_keyboard_key(key: keyboard::Key) -> Option<Note> {
-        match key {
-            keyboard::Key::Character(c) => {
-                return match c.as_str() {
-                    "a" => Some(Note::C),
-                    "w" => Some(Note::Csharp),
-                    "s" => Some(Note::D),
-                    "r" => Some(Note::Dsharp),
-                    "d" => Some(Note::E),
-                    "f" => Some(Note::F),
-                    "t" => Some(Note::Fsharp),
-                    "g" => Some(Note::G),
-                    "y" => Some(Note::Gsharp),
-                    "h" => Some(Note::A),
-                    "j" => Some(Note::B),
-                    "u" => Some(Note::Asharp),
-                    _ => None
-                };
-            },
-            _ => {None}
-        }
+    fn match_keyboard_key(&self, key_name: &str) -> Option<Pitch> {
+        self.settings.keymap.semitones(key_name)
+            .map(|semitones| Pitch::new(Note::C, self.octave).transposed(semitones))
     }
     
     fn update(&mut self, message: Message) { 
         match message { 
+            Message::TransposeSong(semitones) => {
+                if let Some(song) = &self.last_song {
+                    let song = song.transposed(semitones);
+                    self.set_last_song(song);
+                }
+            }
+
+            Message::TransposeToKey(key) => {
+                // the song is taken to be in the key of its key signature,
+                // or the key it was detected in if it has none
+                let from = self.last_song.as_ref()
+                    .and_then(|song| song.key)
+                    .or_else(|| self.detected_keys.first().map(|(key, _)| *key));
+                if let (Some(song), Some(from)) = (&self.last_song, from) {
+                    let song = song.transposed_to_key(from, key);
+                    self.export_status = Some(format!("Transposed from {} to {}", from, key));
+                    self.set_last_song(song);
+                }
+            }
+
+            Message::UseKey(key) => {
+                self.selected_scale = Some(key.root);
+                self.scale_type = key.scale_type;
+            }
+
+            Message::ToggleKeymapEditor => {
+                if self.current_menu == CurrentMenu::Keymap {
+                    self.current_menu = CurrentMenu::Standard;
+                    self.save_settings();
+                } else {
+                    self.current_menu = CurrentMenu::Keymap;
+                }
+            }
+
+            Message::KeymapPresetChange(preset) => {
+                self.settings.keymap = preset.keymap();
+            }
+
+            Message::KeymapKeyChange(index, key) => {
+                if let Some(note) = self.settings.keymap.notes.get_mut(index) {
+                    note.0 = key;
+                    self.settings.keymap.name = "Custom".to_string();
+                }
+            }
+
+            Message::KeymapNoteChange(index, KeymapNote(semitones)) => {
+                if let Some(note) = self.settings.keymap.notes.get_mut(index) {
+                    note.1 = semitones;
+                    self.settings.keymap.name = "Custom".to_string();
+                }
+            }
+
+            Message::KeymapControlChange(control, key) => {
+                self.settings.keymap.set_control(control, key);
+                self.settings.keymap.name = "Custom".to_string();
+            }
+
+            Message::AddKeymapKey => {
+                let semitones = self.settings.keymap.notes.last()
+                    .map_or(0, |(_, semitones)| semitones + 1);
+                self.settings.keymap.notes.push((String::new(), semitones.min(keymap::HIGHEST_SEMITONES)));
+                self.settings.keymap.name = "Custom".to_string();
+            }
+
+            Message::RemoveKeymapKey(index) => {
+                if index < self.settings.keymap.notes.len() {
+                    self.settings.keymap.notes.remove(index);
+                    self.settings.keymap.name = "Custom".to_string();
+                }
+            }
+
             Message::ToggleHelpGUI => {
                 if self.current_menu == CurrentMenu::Help { 
                     self.current_menu = CurrentMenu::Standard
@@ -242,6 +467,73 @@ impl Program {
                 self.volume = value;
             }
 
+            Message::AttackChange(value) => {
+                self.settings.instrument.envelope.attack = value;
+                self.update_instrument();
+            }
+
+            Message::DecayChange(value) => {
+                self.settings.instrument.envelope.decay = value;
+                self.update_instrument();
+            }
+
+            Message::SustainChange(value) => {
+                self.settings.instrument.envelope.sustain = value;
+                self.update_instrument();
+            }
+
+            Message::ReleaseChange(value) => {
+                self.settings.instrument.envelope.release = value;
+                self.update_instrument();
+            }
+
+            Message::WaveformChange(waveform) => {
+                self.settings.instrument.waveform = waveform;
+                self.update_instrument();
+                self.save_settings();
+            }
+
+            Message::PulseWidthChange(value) => {
+                self.settings.instrument.pulse_width = value;
+                self.update_instrument();
+            }
+
+            Message::SaveSettings => {
+                self.save_settings();
+            }
+
+            Message::WavFormatChange(format) => {
+                self.wav_format = format;
+            }
+
+            Message::ExportPathChange(path) => {
+                self.export_path = path;
+                self.pending_overwrite = None;
+            }
+
+            Message::OutputDirectoryChange(directory) => {
+                self.settings.output_directory = std::path::PathBuf::from(directory);
+            }
+
+            Message::ExportMidi => {
+                self.export_song(ExportKind::Midi, false);
+            }
+
+            Message::ExportWav => {
+                self.export_song(ExportKind::Wav, false);
+            }
+
+            Message::ConfirmOverwrite => {
+                if let Some(kind) = self.pending_overwrite {
+                    self.export_song(kind, true);
+                }
+            }
+
+            Message::CancelOverwrite => {
+                self.pending_overwrite = None;
+                self.export_status = None;
+            }
+
             Message::Tick => {
                 if self.is_recording {
                     let now = std::time::Instant::now();
@@ -250,64 +542,154 @@ impl Program {
                     self.time_elapsed = 0.0;
                 }
 
-                // if let Ok(sound_request) = self.sound_channel.lock().unwrap().1.try_recv() {
-                //     let real_note = sound_request.real_note;
-                //     let time = NoteLength::duration_in_seconds(&real_note.length, sound_request.bpm);
-                //     let frequency = RealNote::base_frequencies(real_note.note.clone()) * 2_f32.powf(self.octave);
-                //     let source = rodio::source::SineWave::new(frequency)
-                //         .amplify(0.1)
-                //         .take_duration(Duration::from_secs_f32(time));
-                //     let (_stream, handle) = OutputStream::try_default().expect("Failed to create output stream");
-                //     let sink = Sink::try_new(&handle).expect("Failed to create sink");
+                if let Some(song) = &self.last_song {
+                    self.playback.tick(song, &self.audio_engine);
+                }
+            }
 
-                //     sink.append(source);
-                //     sink.play(); 
-                //     sink.set_volume(sound_request.volume / 10.0);
-                //     sink.sleep_until_end();                    
-                // }
+            Message::ImportPathChange(path) => {
+                self.import_path = path;
+            }
+
+            Message::ImportMidi => {
+                let path = std::path::PathBuf::from(self.import_path.trim());
+                match midi::Midi::midi_file_read(&path) {
+                    Ok(song) => {
+                        self.export_status = Some(format!("Loaded {} notes from {}", song.notes.len(), path.display()));
+                        self.set_last_song(song);
+                    }
+                    Err(e) => {
+                        self.export_status = Some(format!("Import failed: {}", e));
+                    }
+                }
+            }
+
+            Message::PlaybackPlay => {
+                if self.last_song.is_some() {
+                    self.playback.play(&self.audio_engine);
+                }
+            }
+
+            Message::PlaybackPause => {
+                self.playback.pause(&self.audio_engine);
+            }
+
+            Message::PlaybackStop => {
+                self.playback.stop(&self.audio_engine);
             }
 
             Message::Scale(note) => {
                 self.selected_scale = Some(note); 
             }
 
+            Message::ClearScale => {
+                self.selected_scale = None;
+            }
+
+            Message::ScaleTypeChange(scale_type) => {
+                self.scale_type = scale_type;
+            }
+
             Message::KeyPressed(key) => {
-                let note = Self::match_keyboard_key(key);
+                // ignore key repeats while the key is held down
+                if self.keyboard_keys.contains_key(&key) {
+                    return;
+                }
+                let Some(key_name) = Keymap::key_name(&key) else {
+                    return;
+                };
 
-                if let Some(note) = note {
-                    self.buttons_pressed.insert(note, true); 
-                    self.update(Message::Play(note, false));
+                let keymap = &self.settings.keymap;
+                if Keymap::is_key(&keymap.octave_down, &key_name) {
+                    self.keyboard_keys.insert(key, None);
+                    self.update(Message::OctaveChange(self.octave - 1));
+                } else if Keymap::is_key(&keymap.octave_up, &key_name) {
+                    self.keyboard_keys.insert(key, None);
+                    self.update(Message::OctaveChange(self.octave + 1));
+                } else if Keymap::is_key(&keymap.sustain, &key_name) {
+                    self.keyboard_keys.insert(key, None);
+                    self.update(Message::SustainPedal(true));
+                } else if let Some(pitch) = self.match_keyboard_key(&key_name) {
+                    // keys which play the same pitch, such as where the tracker rows
+                    // overlap, share it rather than restarting it
+                    let already_held = self.keyboard_keys.values().any(|held| *held == Some(pitch));
+                    self.keyboard_keys.insert(key, Some(pitch));
+                    if !already_held {
+                        self.update(Message::Play(pitch, false));
+                    }
                 }
             },
 
             Message::KeyReleased(key) => {
-                let note = Self::match_keyboard_key(key);
-                
-                if let Some(note) = note {
-                    self.buttons_pressed.insert(note, false); 
+                let is_sustain_pedal = Keymap::key_name(&key).is_some_and(|key_name| Keymap::is_key(&self.settings.keymap.sustain, &key_name));
+                match self.keyboard_keys.remove(&key) {
+                    Some(Some(pitch)) if !self.keyboard_keys.values().any(|held| *held == Some(pitch)) => {
+                        self.update(Message::EndPlaying(pitch));
+                    }
+                    Some(None) if is_sustain_pedal => self.update(Message::SustainPedal(false)),
+                    _ => {}
+                }
+            },
+
+            Message::SustainPedal(down) => {
+                self.sustain_pedal = down;
+                if !down {
+                    // the notes let go of while the pedal was down end with it
+                    self.end_sustain();
                 }
             },
 
             Message::ToggleRecoring => {
-                if self.is_recording == false{
+                if !self.is_recording{
                     self.start_recording();
                 } else { 
                     let song = self.stop_recording();
-                    midi::Midi::midi_file_create(song);
+                    self.set_last_song(song);
+
+                    let path = ExportKind::Midi.take_path(&self.settings.output_directory, chrono::Local::now());
+                    self.export_path = path.to_string_lossy().into_owned();
+                    self.export_song(ExportKind::Midi, false);
                 }
             },
 
            
-            Message::PlayChords => {
-                self.play_chords = !self.play_chords;
+            Message::ChordModeChange(chord_mode) => {
+                self.chord_mode = chord_mode;
+            }
+
+            Message::ChordQualityChange(chord_quality) => {
+                self.chord_quality = chord_quality;
             }
 
-            Message::PlayAsync => {
-                self.play_async = !self.play_async;
+            Message::InversionChange(inversion) => {
+                self.inversion = inversion;
             }
 
+            Message::VoicingChange(voicing) => {
+                self.voicing = voicing;
+            }
+
+            Message::HoldNotes => {
+                self.hold_notes = !self.hold_notes;
+                let held: Vec<Pitch> = self.held_notes.keys().copied().collect();
+                for pitch in held {
+                    self.release_note(pitch);
+                }
+            }
+
+            // the on screen keyboard starts as near to the octave as its range allows,
+            // without limiting the octave the computer keyboard plays
             Message::OctaveChange(value) => {
-                self.octave = value;
+                self.octave = value.clamp(0, HIGHEST_OCTAVE);
+            }
+
+            Message::KeyboardRangeChange(range) => {
+                self.settings.keyboard_range = range;
+                self.save_settings();
+            }
+
+            Message::KeyZoomChange(value) => {
+                self.settings.key_zoom = value;
             }
 
             Message::CustomBpmChange(value) => {
@@ -320,16 +702,18 @@ impl Program {
                 Self::update_bpm(self, value);
             }
 
-            Message::EndPlaying(note) => {
-                self.buttons_pressed.insert(note, false); // Update pressed state
+            // the key going up only ends its notes when the sustain pedal is up,
+            // otherwise they sound until the pedal is let go of
+            Message::EndPlaying(pitch) => {
+                self.buttons_pressed.insert(pitch, false); // Update pressed state
+                self.played_chords.remove(&pitch);
+                if !self.sustain_pedal {
+                    self.release_note(pitch);
+                }
             }
 
-            Message::Play(note, _gui) => {
-                self.buttons_pressed.insert(note, true); // Update pressed state
-
-                if note == Note::None {
-                    return;
-                }
+            Message::Play(pitch, _gui) => {
+                self.buttons_pressed.insert(pitch, true); // Update pressed state
 
                 let note_length: NoteLength = match self.note_length {
                     5.0 => NoteLength::Whole,
@@ -341,18 +725,25 @@ impl Program {
                 };
 
                 let real_note = RealNote {
-                    note: note,
+                    pitch,
                     length: note_length, 
-                    octave: self.octave,
                 };
 
-                if self.play_chords == false && self.play_async == false {  
-                    real_note.play(self.bpm, self.is_recording, self.volume);
-                } else if self.play_chords == true { 
-                    let chord = Chord::triad_from_note(&real_note);
-                    chord.play(self.bpm, self.is_recording, self.volume);
-                } else if self.play_async == true {               
-                    real_note.play_async(self.bpm, self.is_recording, self.volume);
+                let chord = Chord::from_program(self, real_note);
+
+                // with the sustain pedal down notes sound until it is let go of,
+                // rather than for the note length
+                if self.hold_notes || self.sustain_pedal {
+                    // a key pressed again while its notes are sounding, from being sustained
+                    // or from the mouse and keyboard together, restarts them
+                    self.release_note(pitch);
+                    let voices = chord.start(&self.audio_engine, self.volume);
+                    let velocity = midi::Midi::volume_to_velocity(self.volume);
+                    let notes = chord.notes.into_iter().zip(voices).collect();
+                    self.held_notes.insert(pitch, (notes, Instant::now(), velocity));
+                } else {
+                    chord.play(&self.audio_engine, self.bpm, self.is_recording, self.volume);
+                    self.played_chords.insert(pitch, chord.notes);
                 }
             }
         }
@@ -396,55 +787,47 @@ impl Program {
 // changing Default for Program
 impl Default for Program { 
     fn default() -> Self {
-        let mut buttons_pressed = HashMap::new();
-        for note in [
-            Note::C, Note::Csharp, Note::D, Note::Dsharp, 
-            Note::E, Note::F, Note::Fsharp, Note::G, 
-            Note::Gsharp, Note::A, Note::Asharp, Note::B
-        ].iter() {
-            buttons_pressed.insert(*note, false);
-        }
-
-        // Reading settings.json
-        let settings = match fs::read_to_string("./config/settings.json") {
-            Ok(dp) => dp, 
-            Err(_e) => {
-                println!("An error occured reading settings"); 
-                "[]".to_string()
-            }
-        };
-        let settings_hmap: HashMap<String, bool> = match serde_json::from_str(&settings) {
-            Ok(sp) => sp, 
-            Err(_e) => {
-                println!("An error occured reading settings (bad format)"); 
-                HashMap::from([
-                    ("info_popup".to_string(), false)
-                ])
-            }
-        };
-
-        let current_menu = if *settings_hmap.get("info_popup").unwrap_or(&false) {
+        let settings = Settings::load();
+        let current_menu = if settings.info_popup {
             CurrentMenu::Help
         } else {
             CurrentMenu::Standard
         };
 
+        let audio_engine = AudioEngine::start();
+        audio_engine.send(SoundRequest::SetInstrument(settings.instrument));
+
         Self {
             note_length: 2.0, 
             selected_scale: None,  
-            octave: 4.0,
+            scale_type: ScaleType::Major,
+            octave: 4,
             bpm: 120.0,
             custom_bpm: "120".to_string(),
-            play_chords: false,
-            play_async: true,
+            chord_mode: ChordMode::Single,
+            chord_quality: ChordQuality::Major,
+            inversion: Inversion::Root,
+            voicing: Voicing::Closed,
+            hold_notes: false,
             is_recording: false,
             time_elapsed: 0.0,
             volume: 30.0,
-            buttons_pressed: buttons_pressed,
-            sound_channel: Arc::new(Mutex::new(
-                std::sync::mpsc::channel::<SoundRequest>()
-            )),
-            current_menu: current_menu
+            buttons_pressed: HashMap::new(),
+            keyboard_keys: HashMap::new(),
+            sustain_pedal: false,
+            held_notes: HashMap::new(),
+            played_chords: HashMap::new(),
+            audio_engine,
+            settings,
+            current_menu,
+            last_song: None,
+            playback: Playback::default(),
+            wav_format: WavFormat::Int16,
+            import_path: String::new(),
+            export_path: String::new(),
+            pending_overwrite: None,
+            export_status: None,
+            detected_keys: Vec::new(),
         }
     }
 }
@@ -505,4 +888,23 @@ pub fn main() -> iced::Result {
         .theme(|_| Theme::TokyoNight)
     .window(window_settings)
         .run()
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_helpers::real_note;
+
+    #[test]
+    fn one_key_in_a_chord_mode_names_its_chord() {
+        let mut played_chords = PlayedChords::new();
+        played_chords.insert(Pitch::new(Note::C, 4), Chord::triad_from_note(&real_note(Note::C)).notes);
+
+        let chord = Program::chord_of(&HashMap::new(), &played_chords, &Playback::default());
+        assert_eq!(chord.name(&Spelling::default()), Some("C".to_string()));
+
+        played_chords.remove(&Pitch::new(Note::C, 4));
+        let chord = Program::chord_of(&HashMap::new(), &played_chords, &Playback::default());
+        assert_eq!(chord.name(&Spelling::default()), None);
+    }
+}
@@ -1,64 +1,99 @@
-use midly::{Format, Header, MetaMessage, MidiMessage, Smf, Timing, Track, TrackEvent};
+use midly::{Format, Header, MetaMessage, MidiMessage, Smf, Timing, Track, TrackEvent, TrackEventKind};
 use midly::num::{u28, u24, u7, u4};
-use std::io::Write;
-use std::env;
+use std::collections::{HashMap, VecDeque};
+use std::io::{self, Write};
+use std::fmt;
 use std::fs::{self, File};
-use std::path::PathBuf;
-use crate::{Note, Song};
+use std::path::Path;
+use crate::{Pitch, Song};
+use crate::key::Key;
+use crate::spelling::Spelling;
 
 pub struct Midi {}
 
+// MidiError enum, returned when a midi file cannot be written or read
+// variants:
+// 1. Io                -> The file or its directory could not be created/written/read
+// 2. Parse             -> The file is not a valid standard midi file
+// 3. UnsupportedFormat -> The file is a format 2 (sequential tracks) midi file
+#[derive(Debug)]
+pub enum MidiError {
+    Io(io::Error),
+    Parse(midly::Error),
+    UnsupportedFormat,
+}
+
+impl fmt::Display for MidiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MidiError::Io(e) => write!(f, "could not access midi file: {}", e),
+            MidiError::Parse(e) => write!(f, "not a valid midi file: {}", e),
+            MidiError::UnsupportedFormat => write!(f, "only format 0 and 1 midi files can be imported"),
+        }
+    }
+}
+
+impl std::error::Error for MidiError {}
+
+impl From<io::Error> for MidiError {
+    fn from(e: io::Error) -> Self {
+        MidiError::Io(e)
+    }
+}
+
+impl From<midly::Error> for MidiError {
+    fn from(e: midly::Error) -> Self {
+        MidiError::Parse(e)
+    }
+}
+
 // impliment for Midi
 // functions: 
-// 1. note_to_midi  -> converts note to u7 midi value
-// 2. bpm_to_microseconds_per_beat  -> converts bpm to u24 microseconds per beat
-// 3. midi_file_create -> creates a midi file with the valid info
+// 1. bpm_to_microseconds_per_beat  -> converts bpm to u24 microseconds per beat
+// 2. volume_to_velocity -> converts a volume (0-100) to a midi velocity (1-127)
+//    velocity_to_volume -> converts a midi velocity back to a volume
+// 3. song_to_smf   -> builds the standard midi file for a Song, with its key signature if known
+// 4. write_song    -> encodes a Song and writes it to the given path
+// 5. midi_file_create -> creates a midi file with the valid info at the given path
+// 6. song_from_smf -> converts the notes, tempo and key signature of a standard midi file into a Song
+// 7. midi_file_read -> reads a midi file into a Song
 impl Midi {
-    pub fn note_to_midi(note: Note, octave: f32) -> u7 {
-        let note_index = match note {
-            Note::C => 0,
-            Note::Csharp => 1,
-            Note::D => 2,
-            Note::Dsharp => 3,
-            Note::E => 4,
-            Note::F => 5,
-            Note::Fsharp => 6,
-            Note::G => 7,
-            Note::Gsharp => 8,
-            Note::A => 9,
-            Note::Asharp => 10,
-            Note::B => 11,
-            Note::None => 0, // Default to C when Note is None
-        };
-        let midi_note = 12 * (octave as i32 + 1) + note_index;
+    pub fn bpm_to_microseconds_per_beat(bpm: f32) -> u24 {
+        u24::from((60_000_000.0 / bpm) as u32)
+    }
 
-        u7::new(midi_note as u8)
+    pub fn volume_to_velocity(volume: f32) -> u8 {
+        // a velocity of 0 would be read as a note off
+        (volume / 100.0 * 127.0).round().clamp(1.0, 127.0) as u8
     }
 
-    pub fn bpm_to_microseconds_per_beat(bpm: f32) -> u24 {
-        u24::from((60_000_000.0 / bpm) as u32)
+    pub fn velocity_to_volume(velocity: u8) -> f32 {
+        velocity as f32 / 127.0 * 100.0
     }
 
-    pub fn midi_file_create(song: Song) {
+    pub fn song_to_smf(song: &Song) -> Smf<'static> {
         let header = Header::new(Format::SingleTrack, Timing::Metrical(480.into()));
         let mut smf = Smf::new(header);
     
-        let mut track: Vec<TrackEvent<'_>> = Track::new();
+        let mut track: Vec<TrackEvent<'static>> = Track::new();
         let tempo = MetaMessage::Tempo(Self::bpm_to_microseconds_per_beat(song.bpm));
         track.push(TrackEvent {
             delta: u28::new(0),
             kind: midly::TrackEventKind::Meta(tempo),
         });
+        if let Some(key) = song.key {
+            let (sharps, minor) = Spelling::key_signature(key.root, key.scale_type);
+            track.push(TrackEvent {
+                delta: u28::new(0),
+                kind: midly::TrackEventKind::Meta(MetaMessage::KeySignature(sharps, minor)),
+            });
+        }
     
         let mut events = Vec::new();
         
-        for (note, octave, start_time, duration) in &song.notes {
-            // Skip Note::None entries
-            if *note == Note::None {
-                continue;
-            }
-            
-            let midi_note = Self::note_to_midi(note.clone(), *octave);
+        for (pitch, start_time, duration, velocity) in &song.notes {
+            let midi_note = u7::new(pitch.key());
+            let vel = u7::new((*velocity).clamp(1, 127));
             let beats_per_second = song.bpm / 60.0;
             let start_ticks = (start_time * beats_per_second * 480.0).round() as u32;
             let duration_ticks = (duration * beats_per_second * 480.0).round() as u32;
@@ -69,7 +104,7 @@ impl Midi {
                     channel: u4::new(0),
                     message: MidiMessage::NoteOn {
                         key: midi_note,
-                        vel: u7::new(64),
+                        vel,
                     },
                 },
             ));
@@ -80,13 +115,18 @@ impl Midi {
                     channel: u4::new(0),
                     message: MidiMessage::NoteOff {
                         key: midi_note,
-                        vel: u7::new(64),
+                        vel,
                     },
                 },
             ));
         }
     
-        events.sort_by_key(|(time, _)| *time);
+        // note offs go before note ons on the same tick so that a repeated
+        // key is not cut off by the end of the previous note
+        events.sort_by_key(|(time, event)| {
+            let is_note_on = matches!(event, midly::TrackEventKind::Midi { message: MidiMessage::NoteOn { .. }, .. });
+            (*time, is_note_on)
+        });
     
         let mut last_time = 0;
         for (time, event) in events {
@@ -96,33 +136,213 @@ impl Midi {
             });
             last_time = time;
         }
+
+        track.push(TrackEvent {
+            delta: u28::new(0),
+            kind: midly::TrackEventKind::Meta(MetaMessage::EndOfTrack),
+        });
     
         smf.tracks.push(track);
+        smf
+    }
+
+    pub fn write_song(song: &Song, path: &Path) -> Result<(), MidiError> {
+        let smf = Self::song_to_smf(song);
+        let mut buffer = Vec::new();
+        smf.write_std(&mut buffer)?;
         
-        let output_file: PathBuf;
-
-        if cfg!(target_os = "windows") {
-            let username = env::var("USERNAME").expect("Failed to get USERNAME environment variable");
-            let mut output_dir = PathBuf::from("C:\\Users");
-            output_dir.push(username);
-            output_dir.push("Documents\\RustMusicKeyboard");
-            fs::create_dir_all(&output_dir).expect("Failed to create directory");
-            output_file = output_dir.join("output.mid");
-    
-        } else if cfg!(target_os = "linux") {
-            output_file = PathBuf::from("/tmp/output.mid");
-    
-        } else {
-            output_file = PathBuf::from("output.mid");
+        File::create(path)?.write_all(&buffer)?;
+        Ok(())
+    }
+
+    pub fn midi_file_create(song: &Song, output_file: &Path) -> Result<(), MidiError> {
+        if let Some(directory) = output_file.parent() {
+            fs::create_dir_all(directory)?;
         }
-    
 
-        let buffer = Vec::new();
-        File::create(&output_file)
-            .expect("Failed to create file")
-            .write_all(&buffer)
-            .expect("Failed to write to file");
+        Self::write_song(song, output_file)
+    }
+
+    pub fn song_from_smf(smf: &Smf) -> Result<Song, MidiError> {
+        if smf.header.format == Format::Sequential {
+            return Err(MidiError::UnsupportedFormat);
+        }
+
+        // merge every track into one list of events in order of their tick,
+        // as the tempo map of a format 1 file is usually in its own track
+        let mut events: Vec<(u64, &TrackEventKind)> = Vec::new();
+        for track in &smf.tracks {
+            let mut tick = 0;
+            for event in track {
+                tick += event.delta.as_int() as u64;
+                events.push((tick, &event.kind));
+            }
+        }
+        events.sort_by_key(|(tick, _)| *tick);
+
+        let mut song = Song::default();
+        let mut first_tempo = None;
+        let mut microseconds_per_beat = 500_000.0;
+        let mut last_tick = 0;
+        let mut time = 0.0;
+        // start times and velocities of the notes which are still on, by (channel, key)
+        let mut open_notes: HashMap<(u8, u8), VecDeque<(f64, u8)>> = HashMap::new();
+
+        for (tick, kind) in events {
+            let seconds_per_tick = match smf.header.timing {
+                Timing::Metrical(ticks_per_beat) => microseconds_per_beat / 1_000_000.0 / ticks_per_beat.as_int() as f64,
+                Timing::Timecode(fps, ticks_per_frame) => 1.0 / (fps.as_f32() as f64 * ticks_per_frame as f64),
+            };
+            time += (tick - last_tick) as f64 * seconds_per_tick;
+            last_tick = tick;
+
+            match kind {
+                TrackEventKind::Meta(MetaMessage::Tempo(tempo)) => {
+                    microseconds_per_beat = tempo.as_int() as f64;
+                    first_tempo.get_or_insert(microseconds_per_beat);
+                }
+                TrackEventKind::Meta(MetaMessage::KeySignature(sharps, minor)) => {
+                    song.key.get_or_insert(Key::from_signature(*sharps, *minor));
+                }
+                TrackEventKind::Midi { channel, message } => {
+                    let (key, velocity) = match message {
+                        MidiMessage::NoteOn { key, vel } => (*key, vel.as_int()),
+                        MidiMessage::NoteOff { key, .. } => (*key, 0),
+                        _ => continue,
+                    };
+
+                    let open = open_notes.entry((channel.as_int(), key.as_int())).or_default();
+                    if velocity > 0 {
+                        open.push_back((time, velocity));
+                    } else if let Some((start_time, velocity)) = open.pop_front() {
+                        song.notes.push((Pitch(key.as_int()), start_time as f32, (time - start_time) as f32, velocity));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // notes which are never turned off end with the file
+        for ((_, key), open) in open_notes {
+            for (start_time, velocity) in open {
+                song.notes.push((Pitch(key), start_time as f32, (time - start_time) as f32, velocity));
+            }
+        }
+
+        song.notes.sort_by(|a, b| a.1.total_cmp(&b.1));
+        if let Some(microseconds_per_beat) = first_tempo {
+            song.bpm = (60_000_000.0 / microseconds_per_beat) as f32;
+        }
+        Ok(song)
+    }
+
+    pub fn midi_file_read(path: &Path) -> Result<Song, MidiError> {
+        let bytes = fs::read(path)?;
+        let smf = Smf::parse(&bytes)?;
+        Self::song_from_smf(&smf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Note;
+    use crate::scale::ScaleType;
+
+    #[test]
+    fn written_file_parses_back() {
+        let song = Song {
+            bpm: 120.0,
+            notes: vec![
+                (Pitch::new(Note::C, 4), 0.0, 0.5, 100),
+                (Pitch::new(Note::E, 4), 0.5, 0.5, 40),
+            ],
+            key: None,
+        };
+        let path = std::env::temp_dir().join(format!("rmk-roundtrip-{}.mid", std::process::id()));
+
+        Midi::write_song(&song, &path).unwrap();
+        let bytes = fs::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        let smf = Smf::parse(&bytes).unwrap();
+        assert_eq!(smf.header.format, Format::SingleTrack);
+        assert_eq!(smf.header.timing, Timing::Metrical(480.into()));
+        assert_eq!(smf.tracks.len(), 1);
+
+        let track = &smf.tracks[0];
+        assert_eq!(track[0].kind, TrackEventKind::Meta(MetaMessage::Tempo(500_000.into())));
+
+        let mut tick = 0;
+        let mut notes = Vec::new();
+        for event in track {
+            tick += event.delta.as_int();
+            if let TrackEventKind::Midi { message, .. } = event.kind {
+                match message {
+                    MidiMessage::NoteOn { key, vel } => notes.push((tick, "on", key.as_int(), vel.as_int())),
+                    MidiMessage::NoteOff { key, vel } => notes.push((tick, "off", key.as_int(), vel.as_int())),
+                    _ => {}
+                }
+            }
+        }
+        assert_eq!(notes, vec![
+            (0, "on", 60, 100),
+            (480, "off", 60, 100),
+            (480, "on", 64, 40),
+            (960, "off", 64, 40),
+        ]);
+    }
+
+    #[test]
+    fn exported_song_imports_back() {
+        let song = Song {
+            bpm: 90.0,
+            notes: vec![
+                (Pitch::new(Note::C, 4), 0.0, 1.0, 127),
+                (Pitch::new(Note::G, 3), 0.0, 2.0, 1),
+                (Pitch::new(Note::Asharp, 5), 1.5, 0.25, 80),
+            ],
+            key: Some(Key { root: Note::G, scale_type: ScaleType::NaturalMinor }),
+        };
+
+        let imported = Midi::song_from_smf(&Midi::song_to_smf(&song)).unwrap();
+
+        assert!((imported.bpm - 90.0).abs() < 0.01);
+        assert_eq!(imported.key, song.key);
+        assert_eq!(imported.notes.len(), 3);
+        for ((pitch, start, duration, velocity), expected) in imported.notes.iter().zip(&song.notes) {
+            assert_eq!((*pitch, *velocity), (expected.0, expected.3));
+            assert!((start - expected.1).abs() < 0.01);
+            assert!((duration - expected.2).abs() < 0.01);
+        }
+    }
+
+    #[test]
+    fn format_1_tempo_changes_apply_to_every_track() {
+        let midi_event = |delta: u32, message| TrackEvent {
+            delta: u28::new(delta),
+            kind: TrackEventKind::Midi { channel: u4::new(0), message },
+        };
+        let tempo_event = |delta: u32, microseconds| TrackEvent {
+            delta: u28::new(delta),
+            kind: TrackEventKind::Meta(MetaMessage::Tempo(u24::new(microseconds))),
+        };
+
+        let mut smf = Smf::new(Header::new(Format::Parallel, Timing::Metrical(480.into())));
+        // 120 bpm for the first beat, then 60 bpm
+        smf.tracks.push(vec![tempo_event(0, 500_000), tempo_event(480, 1_000_000)]);
+        smf.tracks.push(vec![
+            midi_event(0, MidiMessage::NoteOn { key: u7::new(60), vel: u7::new(100) }),
+            midi_event(960, MidiMessage::NoteOn { key: u7::new(60), vel: u7::new(0) }),
+            midi_event(0, MidiMessage::NoteOn { key: u7::new(69), vel: u7::new(100) }),
+        ]);
+
+        let song = Midi::song_from_smf(&smf).unwrap();
 
-        println!("MIDI file saved at: {:?}", output_file);
+        assert_eq!(song.bpm, 120.0);
+        assert_eq!(song.notes, vec![
+            (Pitch::new(Note::C, 4), 0.0, 1.5, 100),
+            (Pitch::new(Note::A, 4), 1.5, 0.0, 100),
+        ]);
     }
-}
\ No newline at end of file
+}
@@ -1,20 +1,127 @@
-use std::time::Duration;
-
 use strum_macros::Display;
-use rodio::{self, OutputStream, Sink, Source};
-use crate::{Program, Playable, async_play_note, record_history};
+use std::fmt;
+use std::time::Instant;
+
+use crate::{Playable, record_history};
+use crate::audio::{AudioEngine, VoiceId};
+use crate::midi::Midi;
 
 
 // Note enum defines all notes in Western music
 #[derive(Debug, Hash, Eq, PartialEq, Clone, Copy)]
 pub enum Note { 
-    A, Asharp, B, C, Csharp, D, Dsharp, E, F, Fsharp, G, Gsharp, None
+    A, Asharp, B, C, Csharp, D, Dsharp, E, F, Fsharp, G, Gsharp
 }
 
+// implement the Note enum
+// functions:
+// 1. index      -> The number of semitones the note is above C
+// 2. from_index -> The note the given number of semitones above C
+// 3. is_black   -> Whether the note is played on a black key of a piano
 impl Note {
-    pub const ALL: [Note; 13] = [
-        Note::C, Note::Csharp, Note::D, Note::Dsharp, Note::E, Note::F, Note::Fsharp, Note::G, Note::Gsharp, Note::A, Note::Asharp, Note::B, Note::None
+    pub const ALL: [Note; 12] = [
+        Note::C, Note::Csharp, Note::D, Note::Dsharp, Note::E, Note::F, Note::Fsharp, Note::G, Note::Gsharp, Note::A, Note::Asharp, Note::B
     ];
+
+    pub fn index(&self) -> usize {
+        match self {
+            Note::C => 0,
+            Note::Csharp => 1,
+            Note::D => 2,
+            Note::Dsharp => 3,
+            Note::E => 4,
+            Note::F => 5,
+            Note::Fsharp => 6,
+            Note::G => 7,
+            Note::Gsharp => 8,
+            Note::A => 9,
+            Note::Asharp => 10,
+            Note::B => 11,
+        }
+    }
+
+    pub fn from_index(index: usize) -> Note {
+        Self::ALL[index % 12]
+    }
+
+    pub fn is_black(&self) -> bool {
+        matches!(self, Note::Csharp | Note::Dsharp | Note::Fsharp | Note::Gsharp | Note::Asharp)
+    }
+}
+
+// Pitch struct, an absolute pitch stored as its midi key,
+// where 60 is middle C (C4) and 69 is the A4 440Hz tuning note
+#[derive(Debug, Hash, Eq, PartialEq, Ord, PartialOrd, Clone, Copy)]
+pub struct Pitch(pub u8);
+
+// implement the Pitch struct
+// functions:
+// 1. new        -> The pitch of a Note in the given octave
+// 2. from_key   -> The pitch of a midi key, limited to the midi range
+// 3. note       -> The Note of the pitch, ignoring its octave
+// 4. octave     -> The octave of the pitch, which starts at each C
+// 5. key        -> The midi key of the pitch
+// 6. frequency  -> The frequency of the pitch in equal temperament
+// 7. transposed -> The pitch moved by the given number of semitones
+// 8. from_name  -> The pitch written such as "C#4", "Db4" or "B#3", if it is one
+// also implements:
+// 1. Display    -> Writes the pitch with sharps and its octave, such as "C#4"
+impl Pitch {
+    pub fn new(note: Note, octave: i32) -> Pitch {
+        Self::from_key(12 * (octave + 1) + note.index() as i32)
+    }
+
+    pub fn from_key(key: i32) -> Pitch {
+        Pitch(key.clamp(0, 127) as u8)
+    }
+
+    pub fn note(&self) -> Note {
+        Note::from_index(self.0 as usize)
+    }
+
+    pub fn octave(&self) -> i32 {
+        self.0 as i32 / 12 - 1
+    }
+
+    pub fn key(&self) -> u8 {
+        self.0
+    }
+
+    pub fn frequency(&self) -> f32 {
+        440.0 * 2_f32.powf((self.0 as f32 - 69.0) / 12.0)
+    }
+
+    pub fn transposed(&self, semitones: i32) -> Pitch {
+        Self::from_key(self.0 as i32 + semitones)
+    }
+
+    pub fn from_name(name: &str) -> Option<Pitch> {
+        let mut chars = name.chars();
+        let letter = match chars.next()?.to_ascii_uppercase() {
+            'C' => 0,
+            'D' => 2,
+            'E' => 4,
+            'F' => 5,
+            'G' => 7,
+            'A' => 9,
+            'B' => 11,
+            _ => return None,
+        };
+        let rest = chars.as_str();
+        let octave_start = rest.find(|c| c != '#' && c != 'b')?;
+        let (accidentals, octave) = rest.split_at(octave_start);
+        let accidental: i32 = accidentals.chars().map(|c| if c == '#' { 1 } else { -1 }).sum();
+
+        // the octave belongs to the letter, so Cb5 is the B below C5
+        let key = 12 * (octave.parse::<i32>().ok()? + 1) + letter + accidental;
+        (0..=127).contains(&key).then_some(Pitch(key as u8))
+    }
+}
+
+impl fmt::Display for Pitch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.note(), self.octave())
+    }
 }
 
 // NoteLength enum defines the length of a note
@@ -50,71 +157,95 @@ impl NoteLength {
 }
 
 // RealNote struct, used for playing sounds according
-// to their length and octave
+// to their length and pitch
 // fields: 
-// 1. note           -> Relevant Note enum 
+// 1. pitch          -> The pitch that the note should be played at
 // 2. length         -> Relevant NoteLength enum
-// 3. octave         -> The octave that music should be played at
 #[derive(Debug, Clone)]
 pub struct RealNote { 
-    pub note: Note, 
+    pub pitch: Pitch, 
     pub length: NoteLength, 
-    pub octave: f32,
 }
 
 // implement the RealNote struct
 // functions:
-// 1. base_frequencies     -> Determine the octave 0 frequency for the relevant Note
-// 2. play_sound           -> Plays the note sound in the current thread
-// 3. play_async           -> Plays the note sound in another thread asynchronously
+// 1. frequency            -> The frequency of the note's pitch
+// 2. transposed           -> The same note moved by the given number of semitones
+// 3. play_sound           -> Sends the note to the audio engine for its NoteLength
 // also implements:
-// 1. play (from Playable) -> The same as play_sound
+// 1. play (from Playable)  -> The same as play_sound
+// 2. start (from Playable) -> Starts the note until its voice is released
 impl RealNote { 
-    pub fn base_frequencies(note: Note) -> f32 { 
-        match note {
-            Note::C => 16.35,
-            Note::Csharp => 17.32,
-            Note::D => 18.35,
-            Note::Dsharp => 19.45,
-            Note::E => 20.60,
-            Note::F => 21.83,
-            Note::Fsharp => 23.12,
-            Note::G => 24.50,
-            Note::Gsharp => 25.96,
-            Note::A => 27.50,   
-            Note::Asharp => 29.14,
-            Note::B => 30.87,
-            Note::None => 0.0,
+    pub fn frequency(&self) -> f32 {
+        self.pitch.frequency()
+    }
+
+    pub fn transposed(&self, semitones: i32) -> RealNote {
+        RealNote {
+            pitch: self.pitch.transposed(semitones),
+            length: self.length,
         }
     }
 
-    fn play_sound(&self, bpm: f32, is_recording: bool, volume: f32) {  
+    fn play_sound(&self, engine: &AudioEngine, bpm: f32, is_recording: bool, volume: f32) {  
         let time = NoteLength::duration_in_seconds(&self.length, bpm);
-        let frequency = Self::base_frequencies(self.note.clone()) * 2_f32.powf(self.octave);
-        let source = rodio::source::SineWave::new(frequency)
-            .amplify(0.1)
-            .take_duration(Duration::from_secs_f32(time));
-        let (_stream, handle) = OutputStream::try_default().expect("Failed to create output stream");
-        let sink = Sink::try_new(&handle).expect("Failed to create sink");
 
         if is_recording {
-            record_history(self.clone(), time);
+            record_history(self.clone(), Instant::now(), time, Midi::volume_to_velocity(volume));
         }
-        sink.append(source);
-        sink.play(); 
-        sink.set_volume(volume / 10.0);
-        sink.sleep_until_end();
-    }
-
-    pub fn play_async(&self, bpm: f32, is_recording: bool, volume: f32) { 
-        let notes = vec![self.clone()];
-        async_play_note(&notes, bpm, is_recording, volume);
+        engine.note_on(self.frequency(), volume, Some(time));
     }
 }
 
 // implement Playable trait for RealNote 
 impl Playable for RealNote { 
-    fn play(&self, bpm: f32, is_recording: bool, volume: f32) {
-        self.play_sound(bpm, is_recording, volume);
+    fn play(&self, engine: &AudioEngine, bpm: f32, is_recording: bool, volume: f32) {
+        self.play_sound(engine, bpm, is_recording, volume);
+    }
+
+    fn start(&self, engine: &AudioEngine, volume: f32) -> Vec<VoiceId> {
+        vec![engine.note_on(self.frequency(), volume, None)]
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pitches_convert_to_and_from_notes() {
+        for octave in -1..=8 {
+            for note in Note::ALL {
+                let pitch = Pitch::new(note, octave);
+                assert_eq!((pitch.note(), pitch.octave()), (note, octave));
+            }
+        }
+        assert_eq!(Pitch::new(Note::C, 4), Pitch(60));
+        assert_eq!(Pitch::new(Note::B, 3).transposed(1), Pitch::new(Note::C, 4));
+    }
+
+    #[test]
+    fn pitches_are_written_by_name_and_octave() {
+        for key in 0..=127 {
+            let pitch = Pitch(key);
+            assert_eq!(Pitch::from_name(&pitch.to_string()), Some(pitch));
+        }
+        assert_eq!(Pitch(61).to_string(), "C#4");
+        assert_eq!(Pitch(0).to_string(), "C-1");
+
+        assert_eq!(Pitch::from_name("Db4"), Some(Pitch(61)));
+        assert_eq!(Pitch::from_name("B#3"), Some(Pitch::new(Note::C, 4)));
+        assert_eq!(Pitch::from_name("Cb5"), Some(Pitch::new(Note::B, 4)));
+        assert_eq!(Pitch::from_name("H4"), None);
+        assert_eq!(Pitch::from_name("C#"), None);
+        assert_eq!(Pitch::from_name("G9"), Some(Pitch(127)));
+        assert_eq!(Pitch::from_name("A9"), None);
+    }
+
+    #[test]
+    fn pitches_are_tuned_to_a440() {
+        assert_eq!(Pitch::new(Note::A, 4).frequency(), 440.0);
+        assert!((Pitch::new(Note::C, 0).frequency() - 16.35).abs() < 0.01);
+        assert!((Pitch::new(Note::Fsharp, 5).frequency() - 739.99).abs() < 0.01);
+    }
+}
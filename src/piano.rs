@@ -0,0 +1,300 @@
+// use other files inside this project
+use crate::{Message, Note, Pitch};
+
+// use dependencies
+use iced::{alignment, border::Radius, font::Weight, mouse, Color, Font, Point, Rectangle, Renderer, Size, Theme};
+use iced::widget::canvas::{self, event, Event, Frame, Geometry, Path, Text};
+use strum_macros::Display;
+
+// The lowest and highest keys of an 88 key piano, A0 and C8
+const PIANO_LOWEST_KEY: u8 = 21;
+const PIANO_HIGHEST_KEY: u8 = 108;
+
+// The size of the keys at full zoom, the gap between white keys
+// and the space around the keyboard
+const NATURAL_WIDTH: f32 = 85.0;
+const NATURAL_HEIGHT: f32 = 255.0;
+const ACCIDENTAL_WIDTH: f32 = 63.75;
+const ACCIDENTAL_HEIGHT: f32 = 132.6;
+const KEY_SPACING: f32 = 2.0;
+const PADDING: f32 = 5.0;
+
+// KeyboardRange enum defines how many keys the on screen keyboard shows,
+// each smaller range running from the C of the current octave up to a C
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum KeyboardRange {
+    #[strum(to_string = "2 octaves (25 keys)")]
+    Keys25,
+    #[strum(to_string = "3 octaves (37 keys)")]
+    Keys37,
+    #[strum(to_string = "5 octaves (61 keys)")]
+    Keys61,
+    #[strum(to_string = "Full piano (88 keys)")]
+    Keys88,
+}
+
+// implement the KeyboardRange enum
+// functions:
+// 1. key            -> The name used for the range in settings.json
+// 2. from_key       -> The range with the given name in settings.json
+// 3. highest_octave -> The highest octave the keyboard can start from
+//                      without going above the top of a piano
+// 4. pitches        -> Every pitch shown, from lowest to highest, when the
+//                      keyboard starts at the given octave
+impl KeyboardRange {
+    pub const ALL: [KeyboardRange; 4] = [
+        KeyboardRange::Keys25, KeyboardRange::Keys37, KeyboardRange::Keys61, KeyboardRange::Keys88
+    ];
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            KeyboardRange::Keys25 => "25",
+            KeyboardRange::Keys37 => "37",
+            KeyboardRange::Keys61 => "61",
+            KeyboardRange::Keys88 => "88",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<KeyboardRange> {
+        Self::ALL.into_iter().find(|range| range.key() == key)
+    }
+
+    pub fn highest_octave(&self) -> i32 {
+        match self {
+            KeyboardRange::Keys25 => 6,
+            KeyboardRange::Keys37 => 5,
+            KeyboardRange::Keys61 => 3,
+            // every key is always shown, so the octave only moves the computer keyboard
+            KeyboardRange::Keys88 => 7,
+        }
+    }
+
+    pub fn pitches(&self, octave: i32) -> Vec<Pitch> {
+        let (lowest, highest) = match self {
+            KeyboardRange::Keys88 => (PIANO_LOWEST_KEY, PIANO_HIGHEST_KEY),
+            _ => {
+                let octaves = (8 - self.highest_octave()) as u8;
+                let lowest = Pitch::new(Note::C, octave.clamp(0, self.highest_octave())).key();
+                (lowest, lowest + 12 * octaves)
+            }
+        };
+        (lowest..=highest).map(Pitch).collect()
+    }
+}
+
+// PianoKey struct, a key of the on screen keyboard
+// fields:
+// 1. pitch    -> The pitch the key plays
+// 2. bounds   -> Where the key is drawn, from the top left of the keyboard
+// 3. label    -> The name written on the key
+// 4. in_scale -> Whether the key is in the selected scale
+// 5. pressed  -> Whether the key is held down or being played back
+#[derive(Debug, Clone, PartialEq)]
+pub struct PianoKey {
+    pub pitch: Pitch,
+    pub bounds: Rectangle,
+    pub label: String,
+    pub in_scale: bool,
+    pub pressed: bool,
+}
+
+// Piano struct, the on screen keyboard, drawn on a canvas which
+// plays a key while the mouse is held down on it
+// fields:
+// 1. keys       -> Every key, with the white keys before the black keys drawn over them
+// 2. label_size -> The size of the text on the keys
+//
+// functions:
+// 1. new       -> The keys for the given pitches laid out like a piano,
+//                 with white keys the given zoom of their full width
+// 2. size      -> The size of the whole keyboard
+// 3. key_at    -> The pitch of the key at a point on the keyboard
+// 4. key_color -> The colour a key is filled with
+#[derive(Debug, Clone)]
+pub struct Piano {
+    pub keys: Vec<PianoKey>,
+    label_size: f32,
+}
+
+impl Piano {
+    pub fn new(pitches: &[Pitch], zoom: f32) -> Piano {
+        let natural_width = NATURAL_WIDTH * zoom;
+        let accidental_width = ACCIDENTAL_WIDTH * zoom;
+        let key = |pitch: Pitch, x: f32, width: f32, height: f32| PianoKey {
+            pitch,
+            bounds: Rectangle::new(Point::new(x, PADDING), Size::new(width, height)),
+            label: String::new(),
+            in_scale: true,
+            pressed: false,
+        };
+
+        let mut naturals = Vec::new();
+        let mut accidentals = Vec::new();
+        for pitch in pitches {
+            // a black key sits over the gap between the white keys either side of it
+            let next_natural_x = PADDING + naturals.len() as f32 * (natural_width + KEY_SPACING);
+            if pitch.note().is_black() {
+                let x = next_natural_x - KEY_SPACING / 2.0 - accidental_width / 2.0;
+                accidentals.push(key(*pitch, x, accidental_width, ACCIDENTAL_HEIGHT));
+            } else {
+                naturals.push(key(*pitch, next_natural_x, natural_width, NATURAL_HEIGHT));
+            }
+        }
+        naturals.extend(accidentals);
+
+        Piano {
+            keys: naturals,
+            label_size: (24.0 * zoom).max(10.0),
+        }
+    }
+
+    pub fn size(&self) -> Size {
+        let right = self.keys.iter()
+            .map(|key| key.bounds.x + key.bounds.width)
+            .fold(0.0, f32::max);
+        Size::new(right + PADDING, NATURAL_HEIGHT + 2.0 * PADDING)
+    }
+
+    pub fn key_at(&self, point: Point) -> Option<Pitch> {
+        // black keys are drawn last, so they are on top
+        self.keys.iter().rev()
+            .find(|key| key.bounds.contains(point))
+            .map(|key| key.pitch)
+    }
+
+    fn key_color(key: &PianoKey) -> Color {
+        let color = if key.pitch.note().is_black() { Color::BLACK } else { Color::WHITE };
+        if key.pressed {
+            Color { a: 0.2, ..color }
+        } else if key.in_scale {
+            color
+        } else {
+            Color { a: 0.5, ..color }
+        }
+    }
+}
+
+// the state of the canvas is the key the mouse is holding down
+impl canvas::Program<Message> for Piano {
+    type State = Option<Pitch>;
+
+    fn update(&self, state: &mut Self::State, event: Event, bounds: Rectangle, cursor: mouse::Cursor) -> (event::Status, Option<Message>) {
+        let key = cursor.position_in(bounds).and_then(|point| self.key_at(point));
+
+        match event {
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                if let Some(pitch) = key {
+                    *state = Some(pitch);
+                    return (event::Status::Captured, Some(Message::Play(pitch, true)));
+                }
+            }
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                if let Some(pitch) = state.take() {
+                    return (event::Status::Captured, Some(Message::EndPlaying(pitch)));
+                }
+            }
+            // moving off a key while holding it down lets go of it
+            Event::Mouse(mouse::Event::CursorMoved { .. } | mouse::Event::CursorLeft) => {
+                if let Some(pitch) = state.filter(|pitch| key != Some(*pitch)) {
+                    *state = None;
+                    return (event::Status::Captured, Some(Message::EndPlaying(pitch)));
+                }
+            }
+            _ => {}
+        }
+        (event::Status::Ignored, None)
+    }
+
+    fn draw(&self, _state: &Self::State, renderer: &Renderer, _theme: &Theme, bounds: Rectangle, _cursor: mouse::Cursor) -> Vec<Geometry> {
+        let mut frame = Frame::new(renderer, bounds.size());
+
+        for key in &self.keys {
+            frame.fill(&Path::rounded_rectangle(key.bounds.position(), key.bounds.size(), Radius::from(2)), Self::key_color(key));
+
+            let text_color = if key.pitch.note().is_black() { Color::WHITE } else { Color::BLACK };
+            // each line of the label is written upwards from the bottom of the key
+            for (line_number, line) in key.label.lines().rev().enumerate() {
+                frame.fill_text(Text {
+                    content: line.to_string(),
+                    position: Point::new(
+                        key.bounds.center_x(),
+                        key.bounds.y + key.bounds.height - PADDING - line_number as f32 * self.label_size * 1.3,
+                    ),
+                    color: text_color,
+                    size: self.label_size.into(),
+                    font: Font { weight: Weight::Bold, ..Default::default() },
+                    horizontal_alignment: alignment::Horizontal::Center,
+                    vertical_alignment: alignment::Vertical::Bottom,
+                    ..Text::default()
+                });
+            }
+        }
+
+        vec![frame.into_geometry()]
+    }
+
+    fn mouse_interaction(&self, _state: &Self::State, bounds: Rectangle, cursor: mouse::Cursor) -> mouse::Interaction {
+        match cursor.position_in(bounds).and_then(|point| self.key_at(point)) {
+            Some(_) => mouse::Interaction::Pointer,
+            None => mouse::Interaction::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ranges_run_from_c_to_c() {
+        let keys = KeyboardRange::Keys37.pitches(3);
+        assert_eq!(keys.len(), 37);
+        assert_eq!((keys[0], keys[36]), (Pitch::new(Note::C, 3), Pitch::new(Note::C, 6)));
+
+        // the keyboard never runs past the top C of a piano
+        let keys = KeyboardRange::Keys61.pitches(6);
+        assert_eq!(keys.len(), 61);
+        assert_eq!(keys[60], Pitch::new(Note::C, 8));
+    }
+
+    #[test]
+    fn full_range_is_a_piano() {
+        let keys = KeyboardRange::Keys88.pitches(4);
+        assert_eq!(keys.len(), 88);
+        assert_eq!((keys[0], keys[87]), (Pitch::new(Note::A, 0), Pitch::new(Note::C, 8)));
+        assert_eq!(keys.iter().filter(|pitch| !pitch.note().is_black()).count(), 52);
+    }
+
+    #[test]
+    fn ranges_are_saved_by_key() {
+        for range in KeyboardRange::ALL {
+            assert_eq!(KeyboardRange::from_key(range.key()), Some(range));
+        }
+        assert_eq!(KeyboardRange::from_key("49"), None);
+    }
+
+    #[test]
+    fn black_keys_sit_between_white_keys() {
+        let piano = Piano::new(&KeyboardRange::Keys25.pitches(4), 1.0);
+        assert_eq!(piano.keys.len(), 25);
+        assert_eq!(piano.size(), Size::new(15.0 * 87.0 - 2.0 + 10.0, 265.0));
+
+        // C# is drawn centred on the gap between C and D
+        let c = &piano.keys[0];
+        let c_sharp = piano.keys.iter().find(|key| key.pitch == Pitch::new(Note::Csharp, 4)).unwrap();
+        assert_eq!(c.bounds.x + c.bounds.width + 1.0, c_sharp.bounds.center_x());
+    }
+
+    #[test]
+    fn black_keys_are_hit_before_white_keys() {
+        let piano = Piano::new(&KeyboardRange::Keys25.pitches(4), 1.0);
+
+        // the top of the gap between C and D is C#, the bottom is C or D
+        assert_eq!(piano.key_at(Point::new(91.0, 50.0)), Some(Pitch::new(Note::Csharp, 4)));
+        assert_eq!(piano.key_at(Point::new(80.0, 200.0)), Some(Pitch::new(Note::C, 4)));
+        assert_eq!(piano.key_at(Point::new(100.0, 200.0)), Some(Pitch::new(Note::D, 4)));
+        // between the keys and around the keyboard nothing is hit
+        assert_eq!(piano.key_at(Point::new(91.0, 200.0)), None);
+        assert_eq!(piano.key_at(Point::new(2.0, 100.0)), None);
+    }
+}
@@ -0,0 +1,247 @@
+// use other files inside this project
+use crate::{Pitch, Song};
+use crate::audio::{AudioEngine, SoundRequest, VoiceId};
+use crate::midi::Midi;
+
+// use dependencies
+use std::time::Instant;
+
+// PlaybackState enum defines where the transport is
+// variants:
+// 1. Stopped -> Nothing is playing, the next play starts from the beginning
+// 2. Playing -> Playing since started_at, which was the given seconds into the song
+// 3. Paused  -> Paused the given seconds into the song
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlaybackState {
+    Stopped,
+    Playing { started_at: Instant, from: f32 },
+    Paused { at: f32 },
+}
+
+// Playback struct, which plays a Song through the audio engine
+// fields:
+// 1. state     -> Where the transport is
+// 2. next_note -> The index of the next note of the song to start
+// 3. sounding  -> The notes which have started and not yet ended,
+//                 as (Pitch, end_time, velocity, the voice playing it)
+//
+// functions:
+// 1. position  -> The current position in the song in seconds
+// 2. play      -> Starts or resumes playing the song
+// 3. pause     -> Pauses the song, silencing the sounding notes
+// 4. stop      -> Stops the song and returns to the beginning
+// 5. tick      -> Starts the notes which are due and ends the finished ones
+// 6. sounding_pitches -> The pitches which are currently sounding
+#[derive(Debug, Clone)]
+pub struct Playback {
+    pub state: PlaybackState,
+    next_note: usize,
+    sounding: Vec<(Pitch, f32, u8, VoiceId)>,
+}
+
+impl Default for Playback {
+    fn default() -> Self {
+        Self {
+            state: PlaybackState::Stopped,
+            next_note: 0,
+            sounding: Vec::new(),
+        }
+    }
+}
+
+impl Playback {
+    pub fn position(&self) -> f32 {
+        match self.state {
+            PlaybackState::Stopped => 0.0,
+            PlaybackState::Playing { started_at, from } => from + started_at.elapsed().as_secs_f32(),
+            PlaybackState::Paused { at } => at,
+        }
+    }
+
+    pub fn play(&mut self, engine: &AudioEngine) {
+        match self.state {
+            PlaybackState::Stopped => {
+                self.next_note = 0;
+                self.sounding.clear();
+                self.state = PlaybackState::Playing { started_at: Instant::now(), from: 0.0 };
+            }
+            PlaybackState::Paused { at } => {
+                // restart the notes cut off by the pause for the rest of their length
+                for (pitch, end_time, velocity, voice) in &mut self.sounding {
+                    *voice = Self::note_on(engine, *pitch, *end_time - at, *velocity);
+                }
+                self.state = PlaybackState::Playing { started_at: Instant::now(), from: at };
+            }
+            PlaybackState::Playing { .. } => {}
+        }
+    }
+
+    pub fn pause(&mut self, engine: &AudioEngine) {
+        if let PlaybackState::Playing { .. } = self.state {
+            let at = self.position();
+            self.silence(engine);
+            self.state = PlaybackState::Paused { at };
+        }
+    }
+
+    pub fn stop(&mut self, engine: &AudioEngine) {
+        self.silence(engine);
+        self.sounding.clear();
+        self.next_note = 0;
+        self.state = PlaybackState::Stopped;
+    }
+
+    pub fn tick(&mut self, song: &Song, engine: &AudioEngine) {
+        if !matches!(self.state, PlaybackState::Playing { .. }) {
+            return;
+        }
+        let position = self.position();
+
+        self.sounding.retain(|(_, end_time, _, _)| *end_time > position);
+        while let Some((pitch, start_time, duration, velocity)) = song.notes.get(self.next_note) {
+            if *start_time > position {
+                break;
+            }
+            self.next_note += 1;
+
+            let end_time = start_time + duration;
+            if end_time <= position {
+                continue;
+            }
+            let voice = Self::note_on(engine, *pitch, end_time - position, *velocity);
+            self.sounding.push((*pitch, end_time, *velocity, voice));
+        }
+
+        if self.next_note >= song.notes.len() && self.sounding.is_empty() {
+            self.state = PlaybackState::Stopped;
+            self.next_note = 0;
+        }
+    }
+
+    pub fn sounding_pitches(&self) -> impl Iterator<Item = Pitch> + '_ {
+        self.sounding.iter().map(|(pitch, _, _, _)| *pitch)
+    }
+
+    fn note_on(engine: &AudioEngine, pitch: Pitch, duration: f32, velocity: u8) -> VoiceId {
+        engine.note_on(pitch.frequency(), Midi::velocity_to_volume(velocity), Some(duration))
+    }
+
+    fn silence(&self, engine: &AudioEngine) {
+        // only the voices started by playback, so notes played on the same keys keep sounding
+        for (_, _, _, voice) in &self.sounding {
+            engine.send(SoundRequest::NoteOff { id: *voice });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::Mixer;
+    use crate::synth::Instrument;
+    use crate::Note;
+    use std::sync::mpsc::Receiver;
+    use std::time::Duration;
+
+    fn test_song() -> Song {
+        Song {
+            bpm: 120.0,
+            notes: vec![
+                (Pitch::new(Note::C, 4), 0.0, 1.0, 100),
+                (Pitch::new(Note::E, 4), 0.5, 1.0, 100),
+            ],
+            key: None,
+        }
+    }
+
+    // play as if started the given seconds ago
+    fn playing_since(seconds: f32) -> PlaybackState {
+        PlaybackState::Playing { started_at: Instant::now() - Duration::from_secs_f32(seconds), from: 0.0 }
+    }
+
+    fn requests(receiver: &Receiver<SoundRequest>) -> Vec<SoundRequest> {
+        receiver.try_iter().collect()
+    }
+
+    #[test]
+    fn play_pause_and_stop_move_the_transport() {
+        let (engine, receiver) = AudioEngine::channel();
+        let song = test_song();
+        let mut playback = Playback::default();
+        assert_eq!(playback.position(), 0.0);
+
+        playback.play(&engine);
+        assert!(matches!(playback.state, PlaybackState::Playing { from: 0.0, .. }));
+        playback.state = playing_since(0.75);
+        playback.tick(&song, &engine);
+        assert_eq!(playback.sounding_pitches().collect::<Vec<Pitch>>(), [Pitch::new(Note::C, 4), Pitch::new(Note::E, 4)]);
+        assert_eq!(requests(&receiver).len(), 2);
+
+        playback.pause(&engine);
+        let PlaybackState::Paused { at } = playback.state else {
+            panic!("expected the transport to be paused, found {:?}", playback.state);
+        };
+        assert!(at >= 0.75 && playback.position() == at);
+        assert!(requests(&receiver).iter().all(|request| matches!(request, SoundRequest::NoteOff { .. })));
+
+        // resuming restarts the notes cut off for the rest of their length
+        playback.play(&engine);
+        assert!(matches!(playback.state, PlaybackState::Playing { from, .. } if from == at));
+        let restarted: Vec<f32> = requests(&receiver).iter()
+            .filter_map(|request| match request {
+                SoundRequest::NoteOn { duration, .. } => *duration,
+                _ => None,
+            })
+            .collect();
+        assert_eq!(restarted, [1.0 - at, 1.5 - at]);
+
+        playback.stop(&engine);
+        assert_eq!(playback.state, PlaybackState::Stopped);
+        assert_eq!(playback.position(), 0.0);
+        assert_eq!(playback.sounding_pitches().count(), 0);
+        assert_eq!(requests(&receiver).len(), 2);
+    }
+
+    #[test]
+    fn playback_stops_by_itself_at_the_end_of_the_song() {
+        let (engine, _receiver) = AudioEngine::channel();
+        let song = test_song();
+        let mut playback = Playback::default();
+
+        // pausing or stopping while stopped does nothing
+        playback.pause(&engine);
+        assert_eq!(playback.state, PlaybackState::Stopped);
+
+        playback.play(&engine);
+        playback.state = playing_since(1.25);
+        playback.tick(&song, &engine);
+        assert_eq!(playback.sounding_pitches().collect::<Vec<Pitch>>(), [Pitch::new(Note::E, 4)]);
+
+        playback.state = playing_since(2.0);
+        playback.tick(&song, &engine);
+        assert_eq!(playback.state, PlaybackState::Stopped);
+        assert_eq!(playback.sounding_pitches().count(), 0);
+    }
+
+    #[test]
+    fn stopping_playback_leaves_played_notes_sounding() {
+        let (engine, receiver) = AudioEngine::channel();
+        let mut mixer = Mixer::new(8_000);
+        let mut instrument = Instrument::default();
+        instrument.envelope.release = 0.0;
+        mixer.handle(SoundRequest::SetInstrument(instrument));
+        let mut playback = Playback::default();
+
+        // a key held on the keyboard while the song plays the same note
+        engine.note_on(Pitch::new(Note::C, 4).frequency(), 100.0, None);
+        playback.play(&engine);
+        playback.tick(&test_song(), &engine);
+        playback.stop(&engine);
+
+        for request in requests(&receiver) {
+            mixer.handle(request);
+        }
+        mixer.next_sample();
+        assert!(!mixer.is_silent());
+    }
+}
@@ -0,0 +1,167 @@
+// use other files inside this project
+use crate::Song;
+use crate::audio::{Mixer, SoundRequest, VoiceId, SAMPLE_RATE};
+use crate::midi::Midi;
+use crate::synth::Instrument;
+
+// use dependencies
+use hound::{SampleFormat, WavSpec, WavWriter};
+use std::fs;
+use std::path::Path;
+use strum_macros::Display;
+
+// WavFormat enum defines the sample formats a Song can be rendered to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum WavFormat {
+    #[strum(to_string = "16-bit")]
+    Int16,
+    #[strum(to_string = "24-bit")]
+    Int24,
+    #[strum(to_string = "32-bit float")]
+    Float32,
+}
+
+impl WavFormat {
+    pub const ALL: [WavFormat; 3] = [WavFormat::Int16, WavFormat::Int24, WavFormat::Float32];
+
+    fn spec(&self, sample_rate: u32) -> WavSpec {
+        let (bits_per_sample, sample_format) = match self {
+            WavFormat::Int16 => (16, SampleFormat::Int),
+            WavFormat::Int24 => (24, SampleFormat::Int),
+            WavFormat::Float32 => (32, SampleFormat::Float),
+        };
+
+        WavSpec {
+            channels: 1,
+            sample_rate,
+            bits_per_sample,
+            sample_format,
+        }
+    }
+}
+
+// Render struct, which plays a Song through the Mixer used for live
+// playback without needing an audio device
+// functions:
+// 1. render_song     -> Renders every note of the Song into mono samples
+// 2. write_wav       -> Writes rendered samples to a wav file
+// 3. wav_file_create -> Renders a Song and writes it to a wav file
+pub struct Render {}
+
+impl Render {
+    pub fn render_song(song: &Song, instrument: Instrument, sample_rate: u32) -> Vec<f32> {
+        let mut events: Vec<(u64, SoundRequest)> = song.notes.iter()
+            .enumerate()
+            .map(|(index, (pitch, start_time, duration, velocity))| {
+                let start_sample = (start_time * sample_rate as f32).round() as u64;
+                (start_sample, SoundRequest::NoteOn {
+                    id: VoiceId(index as u64),
+                    frequency: pitch.frequency(),
+                    volume: Midi::velocity_to_volume(*velocity),
+                    duration: Some(*duration),
+                })
+            })
+            .collect();
+        events.sort_by_key(|(start_sample, _)| *start_sample);
+
+        let mut mixer = Mixer::new(sample_rate);
+        mixer.handle(SoundRequest::SetInstrument(instrument));
+
+        let mut samples = Vec::new();
+        let mut events = events.into_iter().peekable();
+        // keep going until every note has started and finished its release
+        while events.peek().is_some() || !mixer.is_silent() {
+            while let Some((_, request)) = events.next_if(|(start_sample, _)| *start_sample <= samples.len() as u64) {
+                mixer.handle(request);
+            }
+            samples.push(mixer.next_sample());
+        }
+        samples
+    }
+
+    pub fn write_wav(samples: &[f32], sample_rate: u32, format: WavFormat, path: &Path) -> Result<(), hound::Error> {
+        let mut writer = WavWriter::create(path, format.spec(sample_rate))?;
+
+        for sample in samples {
+            match format {
+                WavFormat::Int16 => writer.write_sample((sample * i16::MAX as f32) as i16)?,
+                WavFormat::Int24 => writer.write_sample((sample * 8_388_607.0) as i32)?,
+                WavFormat::Float32 => writer.write_sample(*sample)?,
+            }
+        }
+        writer.finalize()
+    }
+
+    pub fn wav_file_create(song: &Song, instrument: Instrument, format: WavFormat, path: &Path) -> Result<(), hound::Error> {
+        if let Some(directory) = path.parent() {
+            fs::create_dir_all(directory)?;
+        }
+
+        let samples = Self::render_song(song, instrument, SAMPLE_RATE);
+        Self::write_wav(&samples, SAMPLE_RATE, format, path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Note, Pitch};
+    use crate::synth::{Envelope, Waveform};
+    use hound::WavReader;
+
+    const SAMPLE_RATE: u32 = 8_000;
+
+    fn test_song() -> Song {
+        Song {
+            bpm: 120.0,
+            notes: vec![(Pitch::new(Note::A, 4), 0.5, 0.25, 64)],
+            key: None,
+        }
+    }
+
+    fn test_instrument() -> Instrument {
+        Instrument {
+            waveform: Waveform::Square,
+            pulse_width: 0.25,
+            envelope: Envelope { attack: 0.0, decay: 0.0, sustain: 1.0, release: 0.1 },
+        }
+    }
+
+    #[test]
+    fn renders_notes_at_their_start_time() {
+        let samples = Render::render_song(&test_song(), test_instrument(), SAMPLE_RATE);
+
+        // 0.5s of silence, 0.25s of the note and 0.1s of release
+        assert_eq!(samples.len(), (0.85 * SAMPLE_RATE as f32) as usize);
+        assert!(samples[..4_000].iter().all(|sample| *sample == 0.0));
+        // a velocity of 64 plays at just over half volume
+        let amplitude = Midi::velocity_to_volume(64) / 100.0;
+        assert!(samples[4_000..6_000].iter().all(|sample| sample.abs() == amplitude));
+        assert!(samples[6_001..].iter().all(|sample| sample.abs() < amplitude));
+    }
+
+    #[test]
+    fn written_wav_reads_back_in_every_format() {
+        let samples = Render::render_song(&test_song(), test_instrument(), SAMPLE_RATE);
+
+        for format in WavFormat::ALL {
+            let path = std::env::temp_dir().join(format!("rmk-render-{:?}-{}.wav", format, std::process::id()));
+            Render::write_wav(&samples, SAMPLE_RATE, format, &path).unwrap();
+
+            let mut reader = WavReader::open(&path).unwrap();
+            let spec = reader.spec();
+            assert_eq!(spec, format.spec(SAMPLE_RATE));
+            assert_eq!(reader.len() as usize, samples.len());
+
+            let peak = match format {
+                WavFormat::Float32 => reader.samples::<f32>().map(|sample| sample.unwrap().abs()).fold(0.0, f32::max),
+                _ => {
+                    let full_scale = (1_i32 << (spec.bits_per_sample - 1)) as f32;
+                    reader.samples::<i32>().map(|sample| sample.unwrap().abs() as f32 / full_scale).fold(0.0, f32::max)
+                }
+            };
+            assert!((peak - 0.504).abs() < 0.001, "{} peaked at {}", format, peak);
+            std::fs::remove_file(&path).unwrap();
+        }
+    }
+}
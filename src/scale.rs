@@ -0,0 +1,171 @@
+// use other files inside this project
+use crate::Note;
+
+// use dependencies
+use strum_macros::Display;
+
+// The steps in semitones between each note of the scales which
+// the other scales are modes of
+const MAJOR: [usize; 7] = [2, 2, 1, 2, 2, 2, 1];
+const HARMONIC_MINOR: [usize; 7] = [2, 1, 2, 2, 1, 3, 1];
+// the ascending form, as used in jazz
+const MELODIC_MINOR: [usize; 7] = [2, 1, 2, 2, 2, 2, 1];
+const MAJOR_PENTATONIC: [usize; 5] = [2, 2, 3, 2, 3];
+const DIMINISHED: [usize; 8] = [2, 1, 2, 1, 2, 1, 2, 1];
+
+// ScaleType enum defines the kinds of scale which can be built on a root note
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Display)]
+pub enum ScaleType {
+    #[strum(to_string = "Major (Ionian)")]
+    Major,
+    Dorian,
+    Phrygian,
+    Lydian,
+    Mixolydian,
+    #[strum(to_string = "Natural minor (Aeolian)")]
+    NaturalMinor,
+    Locrian,
+    #[strum(to_string = "Harmonic minor")]
+    HarmonicMinor,
+    #[strum(to_string = "Locrian ♮6")]
+    LocrianNatural6,
+    #[strum(to_string = "Ionian #5")]
+    IonianSharp5,
+    #[strum(to_string = "Dorian #4")]
+    DorianSharp4,
+    #[strum(to_string = "Phrygian dominant")]
+    PhrygianDominant,
+    #[strum(to_string = "Lydian #2")]
+    LydianSharp2,
+    Ultralocrian,
+    #[strum(to_string = "Melodic minor")]
+    MelodicMinor,
+    #[strum(to_string = "Major pentatonic")]
+    MajorPentatonic,
+    #[strum(to_string = "Minor pentatonic")]
+    MinorPentatonic,
+    Blues,
+    #[strum(to_string = "Whole-tone")]
+    WholeTone,
+    #[strum(to_string = "Diminished (whole-half)")]
+    DiminishedWholeHalf,
+    #[strum(to_string = "Diminished (half-whole)")]
+    DiminishedHalfWhole,
+}
+
+// implement the ScaleType enum
+// functions:
+// 1. intervals -> The semitones between each note of the scale and the next,
+//                 ending back on the root an octave up
+// 2. mode      -> The intervals of the scale starting from the given degree
+//                 (0 being the root) of another scale
+// 3. notes     -> The notes of the scale built on the given root
+impl ScaleType {
+    pub const ALL: [ScaleType; 21] = [
+        ScaleType::Major, ScaleType::Dorian, ScaleType::Phrygian, ScaleType::Lydian,
+        ScaleType::Mixolydian, ScaleType::NaturalMinor, ScaleType::Locrian,
+        ScaleType::HarmonicMinor, ScaleType::LocrianNatural6, ScaleType::IonianSharp5,
+        ScaleType::DorianSharp4, ScaleType::PhrygianDominant, ScaleType::LydianSharp2,
+        ScaleType::Ultralocrian, ScaleType::MelodicMinor, ScaleType::MajorPentatonic,
+        ScaleType::MinorPentatonic, ScaleType::Blues, ScaleType::WholeTone,
+        ScaleType::DiminishedWholeHalf, ScaleType::DiminishedHalfWhole
+    ];
+
+    pub fn intervals(&self) -> Vec<usize> {
+        match self {
+            ScaleType::Major => MAJOR.to_vec(),
+            ScaleType::Dorian => Self::mode(&MAJOR, 1),
+            ScaleType::Phrygian => Self::mode(&MAJOR, 2),
+            ScaleType::Lydian => Self::mode(&MAJOR, 3),
+            ScaleType::Mixolydian => Self::mode(&MAJOR, 4),
+            ScaleType::NaturalMinor => Self::mode(&MAJOR, 5),
+            ScaleType::Locrian => Self::mode(&MAJOR, 6),
+            ScaleType::HarmonicMinor => HARMONIC_MINOR.to_vec(),
+            ScaleType::LocrianNatural6 => Self::mode(&HARMONIC_MINOR, 1),
+            ScaleType::IonianSharp5 => Self::mode(&HARMONIC_MINOR, 2),
+            ScaleType::DorianSharp4 => Self::mode(&HARMONIC_MINOR, 3),
+            ScaleType::PhrygianDominant => Self::mode(&HARMONIC_MINOR, 4),
+            ScaleType::LydianSharp2 => Self::mode(&HARMONIC_MINOR, 5),
+            ScaleType::Ultralocrian => Self::mode(&HARMONIC_MINOR, 6),
+            ScaleType::MelodicMinor => MELODIC_MINOR.to_vec(),
+            ScaleType::MajorPentatonic => MAJOR_PENTATONIC.to_vec(),
+            ScaleType::MinorPentatonic => Self::mode(&MAJOR_PENTATONIC, 4),
+            // the minor pentatonic with the flattened fifth added
+            ScaleType::Blues => vec![3, 2, 1, 1, 3, 2],
+            ScaleType::WholeTone => vec![2; 6],
+            ScaleType::DiminishedWholeHalf => DIMINISHED.to_vec(),
+            ScaleType::DiminishedHalfWhole => Self::mode(&DIMINISHED, 1),
+        }
+    }
+
+    pub fn mode(parent: &[usize], degree: usize) -> Vec<usize> {
+        let mut intervals = parent.to_vec();
+        intervals.rotate_left(degree % parent.len());
+        intervals
+    }
+
+    pub fn notes(&self, root: Note) -> Vec<Note> {
+        let intervals = self.intervals();
+        let mut notes = vec![root];
+        let mut semitones = 0;
+        // the last interval leads back to the root
+        for interval in &intervals[..intervals.len() - 1] {
+            semitones += interval;
+            notes.push(Note::from_index(root.index() + semitones));
+        }
+        notes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_scale_spans_one_octave() {
+        for scale_type in ScaleType::ALL {
+            assert_eq!(scale_type.intervals().iter().sum::<usize>(), 12, "{}", scale_type);
+        }
+    }
+
+    #[test]
+    fn modes_share_the_notes_of_their_parent_scale() {
+        assert_eq!(ScaleType::Dorian.notes(Note::D), vec![
+            Note::D, Note::E, Note::F, Note::G, Note::A, Note::B, Note::C
+        ]);
+        assert_eq!(ScaleType::Locrian.notes(Note::B), vec![
+            Note::B, Note::C, Note::D, Note::E, Note::F, Note::G, Note::A
+        ]);
+        assert_eq!(ScaleType::PhrygianDominant.notes(Note::E), vec![
+            Note::E, Note::F, Note::Gsharp, Note::A, Note::B, Note::C, Note::D
+        ]);
+        assert_eq!(ScaleType::MinorPentatonic.notes(Note::A), vec![
+            Note::A, Note::C, Note::D, Note::E, Note::G
+        ]);
+        assert_eq!(ScaleType::DiminishedHalfWhole.notes(Note::C), vec![
+            Note::C, Note::Csharp, Note::Dsharp, Note::E, Note::Fsharp, Note::G, Note::A, Note::Asharp
+        ]);
+    }
+
+    #[test]
+    fn scales_are_built_from_their_intervals() {
+        assert_eq!(ScaleType::Major.notes(Note::D), vec![
+            Note::D, Note::E, Note::Fsharp, Note::G, Note::A, Note::B, Note::Csharp
+        ]);
+        assert_eq!(ScaleType::NaturalMinor.notes(Note::A), vec![
+            Note::A, Note::B, Note::C, Note::D, Note::E, Note::F, Note::G
+        ]);
+        assert_eq!(ScaleType::HarmonicMinor.notes(Note::A), vec![
+            Note::A, Note::B, Note::C, Note::D, Note::E, Note::F, Note::Gsharp
+        ]);
+        assert_eq!(ScaleType::MelodicMinor.notes(Note::C), vec![
+            Note::C, Note::D, Note::Dsharp, Note::F, Note::G, Note::A, Note::B
+        ]);
+        assert_eq!(ScaleType::Blues.notes(Note::C), vec![
+            Note::C, Note::Dsharp, Note::F, Note::Fsharp, Note::G, Note::Asharp
+        ]);
+        assert_eq!(ScaleType::WholeTone.notes(Note::C), vec![
+            Note::C, Note::D, Note::E, Note::Fsharp, Note::Gsharp, Note::Asharp
+        ]);
+    }
+}
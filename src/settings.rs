@@ -0,0 +1,156 @@
+// use other files inside this project
+use crate::keymap::Keymap;
+use crate::piano::KeyboardRange;
+use crate::synth::{Envelope, Instrument, Waveform};
+
+// use dependencies
+use serde_json::{json, Value};
+use std::{fs, io, path::PathBuf};
+
+const SETTINGS_PATH: &str = "./config/settings.json";
+
+// Settings struct, the values kept in config/settings.json between runs
+// fields:
+// 1. info_popup       -> Whether or not to show the help menu on startup
+// 2. instrument       -> The waveform and envelope used for every voice
+// 3. output_directory -> The directory recordings are saved in
+// 4. keyboard_range   -> How many keys the on screen keyboard shows
+// 5. key_zoom         -> How wide the keys are drawn, 1.0 being full size
+// 6. keymap           -> The computer keys which play notes, change octave and sustain
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    pub info_popup: bool,
+    pub instrument: Instrument,
+    pub output_directory: PathBuf,
+    pub keyboard_range: KeyboardRange,
+    pub key_zoom: f32,
+    pub keymap: Keymap,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            info_popup: false,
+            instrument: Instrument::default(),
+            output_directory: Self::default_output_directory(),
+            keyboard_range: KeyboardRange::Keys25,
+            key_zoom: 0.5,
+            keymap: Keymap::default(),
+        }
+    }
+}
+
+// implement the Settings struct
+// functions:
+// 1. load      -> Reads settings.json, falling back to defaults for anything missing
+// 2. default_output_directory -> Documents/RustMusicKeyboard, or the home directory
+//                                if there is no documents directory
+// 3. from_json -> Reads the settings out of a json value
+// 4. to_json   -> Converts the settings into a json value
+// 5. save      -> Writes the settings back to settings.json
+impl Settings {
+    pub fn load() -> Self {
+        let settings = match fs::read_to_string(SETTINGS_PATH) {
+            Ok(dp) => dp,
+            Err(_e) => {
+                println!("An error occured reading settings");
+                "{}".to_string()
+            }
+        };
+        let settings_json: Value = match serde_json::from_str(&settings) {
+            Ok(sp) => sp,
+            Err(_e) => {
+                println!("An error occured reading settings (bad format)");
+                Value::Null
+            }
+        };
+
+        Self::from_json(&settings_json)
+    }
+
+    pub fn default_output_directory() -> PathBuf {
+        dirs::document_dir()
+            .or_else(dirs::home_dir)
+            .map(|directory| directory.join("RustMusicKeyboard"))
+            .unwrap_or_else(|| PathBuf::from("."))
+    }
+
+    pub fn from_json(settings_json: &Value) -> Self {
+        let defaults = Self::default();
+        let envelope_value = |key: &str, default: f32| {
+            settings_json.get("envelope")
+                .and_then(|envelope| envelope.get(key))
+                .and_then(Value::as_f64)
+                .map_or(default, |value| value as f32)
+        };
+
+        let envelope = defaults.instrument.envelope;
+
+        Self {
+            info_popup: settings_json.get("info_popup")
+                .and_then(Value::as_bool)
+                .unwrap_or(defaults.info_popup),
+            instrument: Instrument {
+                waveform: settings_json.get("waveform")
+                    .and_then(Value::as_str)
+                    .and_then(Waveform::from_key)
+                    .unwrap_or(defaults.instrument.waveform),
+                pulse_width: settings_json.get("pulse_width")
+                    .and_then(Value::as_f64)
+                    .map_or(defaults.instrument.pulse_width, |value| value as f32)
+                    .clamp(0.05, 0.95),
+                envelope: Envelope {
+                    attack: envelope_value("attack", envelope.attack).max(0.0),
+                    decay: envelope_value("decay", envelope.decay).max(0.0),
+                    sustain: envelope_value("sustain", envelope.sustain).clamp(0.0, 1.0),
+                    release: envelope_value("release", envelope.release).max(0.0),
+                },
+            },
+            output_directory: settings_json.get("output_directory")
+                .and_then(Value::as_str)
+                .filter(|directory| !directory.trim().is_empty())
+                .map_or(defaults.output_directory, PathBuf::from),
+            keyboard_range: settings_json.get("keyboard_range")
+                .and_then(Value::as_str)
+                .and_then(KeyboardRange::from_key)
+                .unwrap_or(defaults.keyboard_range),
+            key_zoom: settings_json.get("key_zoom")
+                .and_then(Value::as_f64)
+                .map_or(defaults.key_zoom, |value| value as f32)
+                .clamp(0.25, 1.5),
+            keymap: settings_json.get("keymap")
+                .and_then(Keymap::from_json)
+                .unwrap_or(defaults.keymap),
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let envelope = &self.instrument.envelope;
+
+        json!({
+            "info_popup": self.info_popup,
+            "waveform": self.instrument.waveform.key(),
+            "pulse_width": Self::round(self.instrument.pulse_width),
+            "envelope": {
+                "attack": Self::round(envelope.attack),
+                "decay": Self::round(envelope.decay),
+                "sustain": Self::round(envelope.sustain),
+                "release": Self::round(envelope.release),
+            },
+            "output_directory": self.output_directory.to_string_lossy(),
+            "keyboard_range": self.keyboard_range.key(),
+            "key_zoom": Self::round(self.key_zoom),
+            "keymap": self.keymap.to_json(),
+        })
+    }
+
+    // keeps values such as 0.01 from being written as 0.009999999776482582
+    fn round(value: f32) -> f64 {
+        (value as f64 * 1000.0).round() / 1000.0
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        let settings = serde_json::to_string_pretty(&self.to_json())?;
+        fs::write(SETTINGS_PATH, settings)
+    }
+}
@@ -0,0 +1,112 @@
+// use other files inside this project
+use crate::Pitch;
+use crate::key::Key;
+
+// Song struct, a recorded or imported piece of music
+// fields:
+// 1. notes -> Every note of the song as (Pitch, start_time, duration, velocity)
+// 2. bpm   -> The tempo of the song
+// 3. key   -> The key the song is in, if it is known
+#[derive(Debug, Clone)]
+pub struct Song {
+    pub notes: Vec<(Pitch, f32, f32, u8)>, // Pitch, start_time, duration, velocity
+    pub bpm: f32,
+    pub key: Option<Key>,
+}
+
+impl Default for Song { 
+    fn default() -> Self {
+        Self {
+            bpm: 120.0,
+            notes: vec![],
+            key: None,
+        }
+    }
+}
+
+// implement the Song struct
+// functions:
+// 1. length            -> The time in seconds at which the last note ends
+// 2. transposed        -> The song with every note moved by the given number of semitones
+// 3. transposed_to_key -> The song moved from one key to another, with each note of
+//                         the old scale becoming the same degree of the new scale
+// 4. map_pitches       -> The song in the given key with the pitch of every note replaced
+impl Song {
+    pub fn length(&self) -> f32 {
+        self.notes.iter()
+            .map(|(_, start_time, duration, _)| start_time + duration)
+            .fold(0.0, f32::max)
+    }
+
+    pub fn transposed(&self, semitones: i32) -> Song {
+        self.map_pitches(self.key.map(|key| key.transposed(semitones)), |pitch| pitch.transposed(semitones))
+    }
+
+    pub fn transposed_to_key(&self, from: Key, to: Key) -> Song {
+        let (from_root, to_root) = (from.root.index() as i32, to.root.index() as i32);
+        // the tonic moves by the smallest interval, up to a tritone up or down
+        let root_shift = (to_root - from_root + 6).rem_euclid(12) - 6;
+
+        // the semitones from the tonic to each degree of the scales
+        let degrees = |key: Key| key.scale_type.intervals().iter()
+            .scan(0, |semitones, interval| {
+                let degree = *semitones;
+                *semitones += *interval as i32;
+                Some(degree)
+            })
+            .collect::<Vec<i32>>();
+        let (from_degrees, to_degrees) = (degrees(from), degrees(to));
+
+        self.map_pitches(Some(to), |pitch| {
+            let above_tonic = (pitch.key() as i32 - from_root).rem_euclid(12);
+            // notes outside the scale keep their distance from the degree below them
+            let (degree, offset) = from_degrees.iter().enumerate()
+                .rev()
+                .find(|(_, semitones)| **semitones <= above_tonic)
+                .map_or((0, above_tonic), |(degree, semitones)| (degree, above_tonic - semitones));
+            let new_above_tonic = to_degrees.get(degree).copied().unwrap_or(from_degrees[degree]) + offset;
+
+            pitch.transposed(root_shift + new_above_tonic - above_tonic)
+        })
+    }
+
+    fn map_pitches(&self, key: Option<Key>, map: impl Fn(Pitch) -> Pitch) -> Song {
+        let notes = self.notes.iter()
+            .map(|(pitch, start_time, duration, velocity)| (map(*pitch), *start_time, *duration, *velocity))
+            .collect();
+
+        Song { notes, bpm: self.bpm, key }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Note;
+    use crate::scale::ScaleType;
+    use crate::test_helpers::{song_of, song_pitches};
+
+    #[test]
+    fn transposing_carries_across_octaves() {
+        let song = song_of(&[(Note::A, 4, 1.0), (Note::B, 4, 1.0), (Note::C, 5, 1.0)]);
+
+        assert_eq!(song_pitches(&song.transposed(3)), vec![(Note::C, 5), (Note::D, 5), (Note::Dsharp, 5)]);
+        assert_eq!(song_pitches(&song.transposed(-10)), vec![(Note::B, 3), (Note::Csharp, 4), (Note::D, 4)]);
+    }
+
+    #[test]
+    fn transposing_to_a_key_maps_scale_degrees() {
+        let c_major = Key { root: Note::C, scale_type: ScaleType::Major };
+        let a_minor = Key { root: Note::A, scale_type: ScaleType::NaturalMinor };
+        let d_major = Key { root: Note::D, scale_type: ScaleType::Major };
+        let song = song_of(&[(Note::C, 4, 1.0), (Note::E, 4, 1.0), (Note::G, 4, 1.0), (Note::Fsharp, 4, 1.0), (Note::B, 4, 1.0)]);
+
+        // each degree of C major becomes the same degree of A minor,
+        // with the F# between F and G landing between D and E
+        assert_eq!(song_pitches(&song.transposed_to_key(c_major, a_minor)), vec![
+            (Note::A, 3), (Note::C, 4), (Note::E, 4), (Note::Dsharp, 4), (Note::G, 4)
+        ]);
+        assert_eq!(song_pitches(&song.transposed_to_key(c_major, d_major)), song_pitches(&song.transposed(2)));
+        assert_eq!(song.transposed_to_key(c_major, a_minor).key, Some(a_minor));
+    }
+}
@@ -0,0 +1,208 @@
+// use other files inside this project
+use crate::{Note, Pitch};
+use crate::scale::ScaleType;
+
+// use dependencies
+use std::fmt;
+
+// The letter names of the white keys and the semitones they are above C
+const LETTERS: [(char, i32); 7] = [('C', 0), ('D', 2), ('E', 4), ('F', 5), ('G', 7), ('A', 9), ('B', 11)];
+
+// The number of letters from the root to a note the given number of semitones
+// above it in a chord, such as 2 for a 3rd (C to E) and 4 for a 5th (C to G)
+const CHORD_LETTER_STEPS: [usize; 12] = [0, 1, 1, 2, 2, 3, 4, 4, 4, 5, 6, 6];
+
+// SpelledNote struct, a note written as a letter with its sharps or flats
+// fields:
+// 1. letter     -> The index of the letter in LETTERS, C being 0
+// 2. accidental -> The number of sharps (positive) or flats (negative)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpelledNote {
+    pub letter: usize,
+    pub accidental: i32,
+}
+
+// allows SpelledNote to be converted to String, such as "Bb" or "F##"
+impl fmt::Display for SpelledNote {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let accidental = if self.accidental >= 0 { "#" } else { "b" };
+        write!(f, "{}{}", LETTERS[self.letter].0, accidental.repeat(self.accidental.unsigned_abs() as usize))
+    }
+}
+
+// implement the SpelledNote struct
+// functions:
+// 1. new      -> The given letter with whatever accidental makes it the given note
+// 2. octave_of -> The octave the spelling of the pitch is written in, which is
+//                 the octave below for B# and the octave above for Cb
+impl SpelledNote {
+    pub fn new(letter: usize, note: Note) -> SpelledNote {
+        let letter = letter % 7;
+        SpelledNote {
+            letter,
+            accidental: (note.index() as i32 - LETTERS[letter].1 + 6).rem_euclid(12) - 6,
+        }
+    }
+
+    pub fn octave_of(&self, pitch: Pitch) -> i32 {
+        (pitch.key() as i32 - self.accidental) / 12 - 1
+    }
+}
+
+// Spelling struct, how each of the twelve notes is written in a key
+// fields:
+// 1. names -> The spelling of each note, by Note::index
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Spelling {
+    names: [SpelledNote; 12],
+}
+
+// without a key every black key is written as a sharp
+impl Default for Spelling {
+    fn default() -> Self {
+        Self::with_accidentals(1)
+    }
+}
+
+// implement the Spelling struct
+// functions:
+// 1. with_accidentals -> Every black key written as a sharp (1) or flat (-1)
+// 2. for_key       -> The spelling of the notes of a scale, with each letter used once
+//                     in seven note scales, choosing whichever tonic needs fewer accidentals
+// 3. spell_scale   -> The notes of a scale spelt upwards in letter order from the tonic
+// 4. key_signature -> The sharps (positive) or flats (negative) of the major or minor
+//                     key of a scale, and whether it is minor
+// 5. name          -> How the note is written
+// 6. pitch_name    -> How the pitch is written, with its octave
+// 7. chord_note    -> How the note the given semitones above a chord's root is written
+impl Spelling {
+    pub fn with_accidentals(accidental: i32) -> Spelling {
+        let mut names = [SpelledNote { letter: 0, accidental: 0 }; 12];
+        for (index, name) in names.iter_mut().enumerate() {
+            // white keys keep their own letter, black keys take the letter below or above
+            let letter = LETTERS.iter().position(|(_, semitones)| *semitones == index as i32)
+                .or_else(|| LETTERS.iter().position(|(_, semitones)| *semitones == index as i32 - accidental))
+                .unwrap_or(0);
+            *name = SpelledNote::new(letter, Note::from_index(index));
+        }
+        Spelling { names }
+    }
+
+    pub fn for_key(root: Note, scale_type: ScaleType) -> Spelling {
+        // scales without seven notes are spelt like the major or minor scale they are closest to
+        let intervals = scale_type.intervals();
+        let degrees = scale_type.notes(root);
+        let is_minor = degrees.contains(&Note::from_index(root.index() + 3)) && !degrees.contains(&Note::from_index(root.index() + 4));
+        let seven_note_scale = if intervals.len() == 7 {
+            scale_type
+        } else if is_minor {
+            ScaleType::NaturalMinor
+        } else {
+            ScaleType::Major
+        };
+
+        let scale = Self::spell_scale(root, seven_note_scale);
+        let sharps: i32 = scale.iter().map(|name| name.accidental).sum();
+        let mut spelling = Self::with_accidentals(if sharps < 0 { -1 } else { 1 });
+        for (note, name) in seven_note_scale.notes(root).iter().zip(scale) {
+            spelling.names[note.index()] = name;
+        }
+        spelling
+    }
+
+    pub fn spell_scale(root: Note, scale_type: ScaleType) -> Vec<SpelledNote> {
+        let notes = scale_type.notes(root);
+        // the tonic can be written on its own letter or the ones either side,
+        // and flats are chosen when both need as many accidentals (Gb rather than F#)
+        (0..7).rev()
+            .map(|letter| SpelledNote::new(letter, root))
+            .filter(|tonic| tonic.accidental.abs() <= 1)
+            .map(|tonic| notes.iter()
+                .enumerate()
+                .map(|(degree, note)| SpelledNote::new(tonic.letter + degree, *note))
+                .collect::<Vec<SpelledNote>>())
+            .min_by_key(|scale| scale.iter().map(|name| name.accidental.abs()).sum::<i32>())
+            .unwrap_or_default()
+    }
+
+    pub fn key_signature(root: Note, scale_type: ScaleType) -> (i8, bool) {
+        let notes = scale_type.notes(root);
+        let is_minor = notes.contains(&Note::from_index(root.index() + 3)) && !notes.contains(&Note::from_index(root.index() + 4));
+        let key = if is_minor { ScaleType::NaturalMinor } else { ScaleType::Major };
+
+        let sharps: i32 = Self::spell_scale(root, key).iter().map(|name| name.accidental).sum();
+        (sharps as i8, is_minor)
+    }
+
+    pub fn name(&self, note: Note) -> SpelledNote {
+        self.names[note.index()]
+    }
+
+    pub fn pitch_name(&self, pitch: Pitch) -> String {
+        let name = self.name(pitch.note());
+        format!("{}{}", name, name.octave_of(pitch))
+    }
+
+    pub fn chord_note(root: SpelledNote, semitones: usize, note: Note) -> SpelledNote {
+        SpelledNote::new(root.letter + CHORD_LETTER_STEPS[semitones % 12], note)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(spelling: &Spelling, notes: &[Note]) -> Vec<String> {
+        notes.iter().map(|note| spelling.name(*note).to_string()).collect()
+    }
+
+    #[test]
+    fn scales_use_each_letter_once() {
+        let f_major = Spelling::for_key(Note::F, ScaleType::Major);
+        assert_eq!(names(&f_major, &ScaleType::Major.notes(Note::F)), ["F", "G", "A", "Bb", "C", "D", "E"]);
+
+        let b_major = Spelling::for_key(Note::B, ScaleType::Major);
+        assert_eq!(b_major.name(Note::Asharp).to_string(), "A#");
+
+        let f_sharp_harmonic_minor = Spelling::for_key(Note::Fsharp, ScaleType::HarmonicMinor);
+        assert_eq!(names(&f_sharp_harmonic_minor, &ScaleType::HarmonicMinor.notes(Note::Fsharp)), ["F#", "G#", "A", "B", "C#", "D", "E#"]);
+
+        let g_flat_lydian = Spelling::for_key(Note::Fsharp, ScaleType::Lydian);
+        assert_eq!(names(&g_flat_lydian, &[Note::Fsharp, Note::C]), ["Gb", "C"]);
+        let a_flat_major = Spelling::for_key(Note::Gsharp, ScaleType::Major);
+        assert_eq!(names(&a_flat_major, &[Note::Gsharp, Note::Csharp]), ["Ab", "Db"]);
+
+        let e_flat_minor = Spelling::for_key(Note::Dsharp, ScaleType::NaturalMinor);
+        assert_eq!(e_flat_minor.name(Note::B).to_string(), "Cb");
+    }
+
+    #[test]
+    fn notes_outside_the_scale_follow_the_key_signature() {
+        let f_blues = Spelling::for_key(Note::F, ScaleType::Blues);
+        assert_eq!(names(&f_blues, &ScaleType::Blues.notes(Note::F)), ["F", "Ab", "Bb", "B", "C", "Eb"]);
+
+        assert_eq!(names(&Spelling::default(), &[Note::Csharp, Note::Asharp]), ["C#", "A#"]);
+        assert_eq!(names(&Spelling::with_accidentals(-1), &[Note::Csharp, Note::Asharp]), ["Db", "Bb"]);
+    }
+
+    #[test]
+    fn pitches_are_written_in_the_octave_of_their_letter() {
+        let c_sharp_harmonic_minor = Spelling::for_key(Note::Csharp, ScaleType::HarmonicMinor);
+        assert_eq!(c_sharp_harmonic_minor.pitch_name(Pitch::new(Note::C, 4)), "B#3");
+
+        let g_flat_major = Spelling::for_key(Note::Fsharp, ScaleType::Major);
+        assert_eq!(g_flat_major.pitch_name(Pitch::new(Note::Fsharp, 4)), "Gb4");
+        assert_eq!(g_flat_major.pitch_name(Pitch::new(Note::B, 4)), "Cb5");
+        let c_flat = SpelledNote::new(0, Note::B);
+        assert_eq!(c_flat.to_string(), "Cb");
+        assert_eq!(c_flat.octave_of(Pitch::new(Note::B, 4)), 5);
+    }
+
+    #[test]
+    fn key_signatures_count_sharps_and_flats() {
+        assert_eq!(Spelling::key_signature(Note::D, ScaleType::Major), (2, false));
+        assert_eq!(Spelling::key_signature(Note::F, ScaleType::Major), (-1, false));
+        assert_eq!(Spelling::key_signature(Note::A, ScaleType::HarmonicMinor), (0, true));
+        assert_eq!(Spelling::key_signature(Note::Asharp, ScaleType::NaturalMinor), (-5, true));
+    }
+}
@@ -0,0 +1,218 @@
+// use dependencies
+use std::f32::consts::TAU;
+use strum_macros::Display;
+
+// Waveform enum defines the shape of the wave an oscillator plays
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
+pub enum Waveform {
+    Sine,
+    Square,
+    Sawtooth,
+    Triangle,
+    #[strum(to_string = "Pulse width")]
+    Pulse,
+    Noise,
+    #[strum(to_string = "Organ drawbars")]
+    Organ,
+}
+
+impl Waveform {
+    pub const ALL: [Waveform; 7] = [
+        Waveform::Sine, Waveform::Square, Waveform::Sawtooth, Waveform::Triangle,
+        Waveform::Pulse, Waveform::Noise, Waveform::Organ
+    ];
+
+    // the name used for the waveform in settings.json
+    pub fn key(&self) -> &'static str {
+        match self {
+            Waveform::Sine => "sine",
+            Waveform::Square => "square",
+            Waveform::Sawtooth => "sawtooth",
+            Waveform::Triangle => "triangle",
+            Waveform::Pulse => "pulse",
+            Waveform::Noise => "noise",
+            Waveform::Organ => "organ",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Waveform> {
+        Self::ALL.into_iter().find(|waveform| waveform.key() == key)
+    }
+}
+
+// The drawbars of a tonewheel organ as (harmonic of the played note, level 0-8),
+// set to the classic "888800000" registration
+const DRAWBARS: [(f32, f32); 9] = [
+    (0.5, 8.0), (1.5, 8.0), (1.0, 8.0), (2.0, 8.0), (3.0, 0.0),
+    (4.0, 0.0), (5.0, 0.0), (6.0, 0.0), (8.0, 0.0)
+];
+
+// Oscillator struct, which produces the raw wave for a voice
+// fields:
+// 1. waveform    -> The shape of the wave
+// 2. pulse_width -> The part of each cycle (0.0 to 1.0) which is high for Waveform::Pulse
+// 3. phase       -> The position in the wave, counted over two cycles so that
+//                   the organ's sub-octave drawbars stay continuous
+// 4. noise       -> The state of the random number generator for Waveform::Noise
+#[derive(Debug, Clone)]
+pub struct Oscillator {
+    waveform: Waveform,
+    pulse_width: f32,
+    phase: f32,
+    noise: u32,
+}
+
+impl Oscillator {
+    pub fn new(waveform: Waveform, pulse_width: f32) -> Self {
+        Self {
+            waveform,
+            pulse_width,
+            phase: 0.0,
+            noise: 0x9E37_79B9,
+        }
+    }
+
+    pub fn next_sample(&mut self, frequency: f32, sample_rate: u32) -> f32 {
+        let cycle = self.phase.fract();
+        let sample = match self.waveform {
+            Waveform::Sine => (cycle * TAU).sin(),
+            Waveform::Square => if cycle < 0.5 { 1.0 } else { -1.0 },
+            Waveform::Sawtooth => 2.0 * cycle - 1.0,
+            Waveform::Triangle => 1.0 - 4.0 * (cycle - 0.5).abs(),
+            Waveform::Pulse => if cycle < self.pulse_width { 1.0 } else { -1.0 },
+            Waveform::Noise => {
+                // xorshift32
+                self.noise ^= self.noise << 13;
+                self.noise ^= self.noise >> 17;
+                self.noise ^= self.noise << 5;
+                self.noise as f32 / u32::MAX as f32 * 2.0 - 1.0
+            }
+            Waveform::Organ => {
+                let total: f32 = DRAWBARS.iter().map(|(_, level)| level).sum();
+                DRAWBARS.iter()
+                    .map(|(harmonic, level)| (self.phase * harmonic * TAU).sin() * level)
+                    .sum::<f32>() / total
+            }
+        };
+
+        self.phase = (self.phase + frequency / sample_rate as f32) % 2.0;
+        sample
+    }
+}
+
+// Instrument struct, the settings used to synthesise every voice
+// fields:
+// 1. waveform    -> The waveform played by the oscillator
+// 2. pulse_width -> The pulse width used by Waveform::Pulse
+// 3. envelope    -> The envelope applied to the volume
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Instrument {
+    pub waveform: Waveform,
+    pub pulse_width: f32,
+    pub envelope: Envelope,
+}
+
+impl Default for Instrument {
+    fn default() -> Self {
+        Self {
+            waveform: Waveform::Sine,
+            pulse_width: 0.25,
+            envelope: Envelope::default(),
+        }
+    }
+}
+
+// Envelope struct, the attack/decay/sustain/release shape
+// applied to the volume of every voice
+// fields:
+// 1. attack  -> Seconds taken to rise from silence to full volume
+// 2. decay   -> Seconds taken to fall from full volume to the sustain level
+// 3. sustain -> The level (0.0 to 1.0) held until the note is released
+// 4. release -> Seconds taken to fall to silence after the note is released
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Envelope {
+    pub attack: f32,
+    pub decay: f32,
+    pub sustain: f32,
+    pub release: f32,
+}
+
+impl Default for Envelope {
+    fn default() -> Self {
+        Self {
+            attack: 0.01,
+            decay: 0.1,
+            sustain: 0.8,
+            release: 0.2,
+        }
+    }
+}
+
+// implement the Envelope struct
+// functions:
+// 1. held_level    -> The level of a held note the given seconds after it started
+// 2. release_level -> The level the given seconds after a note was released
+//                     from the given level
+impl Envelope {
+    pub fn held_level(&self, time: f32) -> f32 {
+        if time < self.attack {
+            time / self.attack
+        } else if time < self.attack + self.decay {
+            1.0 - (1.0 - self.sustain) * (time - self.attack) / self.decay
+        } else {
+            self.sustain
+        }
+    }
+
+    pub fn release_level(&self, from: f32, time: f32) -> f32 {
+        if time >= self.release {
+            0.0
+        } else {
+            from * (1.0 - time / self.release)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn waveforms_stay_in_range() {
+        for waveform in Waveform::ALL {
+            let mut oscillator = Oscillator::new(waveform, 0.25);
+            for _ in 0..2_000 {
+                let sample = oscillator.next_sample(261.63, 44_100);
+                assert!((-1.0..=1.0).contains(&sample), "{} produced {}", waveform, sample);
+            }
+        }
+    }
+
+    #[test]
+    fn waveform_keys_round_trip() {
+        for waveform in Waveform::ALL {
+            assert_eq!(Waveform::from_key(waveform.key()), Some(waveform));
+        }
+    }
+
+    #[test]
+    fn envelope_follows_each_stage() {
+        let envelope = Envelope { attack: 0.1, decay: 0.2, sustain: 0.5, release: 0.4 };
+
+        assert_eq!(envelope.held_level(0.0), 0.0);
+        assert!((envelope.held_level(0.05) - 0.5).abs() < 1e-6);
+        assert!((envelope.held_level(0.2) - 0.75).abs() < 1e-6);
+        assert_eq!(envelope.held_level(1.0), 0.5);
+
+        assert!((envelope.release_level(0.5, 0.2) - 0.25).abs() < 1e-6);
+        assert_eq!(envelope.release_level(0.5, 0.4), 0.0);
+    }
+
+    #[test]
+    fn zero_length_stages_do_not_divide_by_zero() {
+        let envelope = Envelope { attack: 0.0, decay: 0.0, sustain: 0.7, release: 0.0 };
+
+        assert_eq!(envelope.held_level(0.0), 0.7);
+        assert_eq!(envelope.release_level(0.7, 0.0), 0.0);
+    }
+}
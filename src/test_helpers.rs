@@ -0,0 +1,31 @@
+// use other files inside this project
+use crate::{Chord, Note, NoteLength, Pitch, RealNote};
+use crate::song::Song;
+
+// The fixtures shared by the tests of several files
+// functions:
+// 1. song_of        -> A song of the given (note, octave, duration), each starting a second after the last
+// 2. song_pitches   -> The note and octave of every note of a song
+// 3. real_note      -> A quarter note in the fourth octave
+// 4. chord_pitches  -> The note and octave of every note of a chord
+pub fn song_of(notes: &[(Note, i32, f32)]) -> Song {
+    Song {
+        bpm: 120.0,
+        notes: notes.iter().enumerate()
+            .map(|(index, (note, octave, duration))| (Pitch::new(*note, *octave), index as f32, *duration, 64))
+            .collect(),
+        key: None,
+    }
+}
+
+pub fn song_pitches(song: &Song) -> Vec<(Note, i32)> {
+    song.notes.iter().map(|(pitch, _, _, _)| (pitch.note(), pitch.octave())).collect()
+}
+
+pub fn real_note(note: Note) -> RealNote {
+    RealNote { pitch: Pitch::new(note, 4), length: NoteLength::Quarter }
+}
+
+pub fn chord_pitches(chord: &Chord) -> Vec<(Note, i32)> {
+    chord.notes.iter().map(|note| (note.pitch.note(), note.pitch.octave())).collect()
+}